@@ -0,0 +1,102 @@
+//! Compares the broadphase implementations on the scene1 asteroid workload,
+//! scaled up to thousands of bodies at the same density.
+//!
+//! Run with `cargo bench --target <host triple>`, since the crate otherwise
+//! defaults to building for wasm.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use nalgebra::Vector2;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use wasm_physics::broadphase::{Broadphase, BvhBroadphase, SweepAndPrune};
+use wasm_physics::bvh::aabb::AABB;
+
+/// scene1 spawns this many asteroids in the square [-1,1]²
+const SCENE1_ASTEROIDS: usize = 20;
+
+struct Asteroid {
+  pos: Vector2<f32>,
+  vel: Vector2<f32>,
+  volume: AABB
+}
+
+/// Asteroids as spawned by scene1, in a world enlarged so that
+/// the density of asteroids matches scene1.
+struct Workload {
+  half_size: f32,
+  asteroids: Vec<Asteroid>
+}
+
+impl Workload {
+  fn new(n: usize) -> Self {
+    let mut rng = StdRng::seed_from_u64(n as u64);
+    let half_size = (n as f32 / SCENE1_ASTEROIDS as f32).sqrt();
+
+    let asteroids = (0..n).map(|_| {
+      let pos = Vector2::new(rng.gen_range(-half_size, half_size), rng.gen_range(-half_size, half_size));
+      let vel = Vector2::new(rng.gen_range(-0.001, 0.001), rng.gen_range(-0.001, 0.001));
+      let volume = AABB::new(Vector2::new(-0.08, -0.08), Vector2::new(0.08, 0.08));
+      Asteroid { pos, vel, volume }
+    }).collect();
+
+    Workload { half_size, asteroids }
+  }
+
+  /// same motion as physics_system, wrapping positions around the world
+  fn step(&mut self) {
+    let size = 2.0 * self.half_size;
+    for asteroid in &mut self.asteroids {
+      asteroid.pos += asteroid.vel;
+      for i in 0..2 {
+        if asteroid.pos[i] < -self.half_size { asteroid.pos[i] += size; }
+        if asteroid.pos[i] >  self.half_size { asteroid.pos[i] -= size; }
+      }
+    }
+  }
+
+  fn populate(&self, broadphase: &mut dyn Broadphase<usize>) {
+    for (i, asteroid) in self.asteroids.iter().enumerate() {
+      broadphase.insert(i, asteroid.volume.translate(asteroid.pos));
+    }
+  }
+
+  /// one tick: move every asteroid, update the broadphase, and collect pairs
+  fn tick(&mut self, broadphase: &mut dyn Broadphase<usize>, pairs: &mut Vec<(usize, usize)>) {
+    self.step();
+    for (i, asteroid) in self.asteroids.iter().enumerate() {
+      broadphase.update(i, asteroid.volume.translate(asteroid.pos), asteroid.vel);
+    }
+
+    pairs.clear();
+    broadphase.for_each_pair(&mut |a, b| pairs.push((a, b)));
+  }
+}
+
+type MakeBroadphase = fn() -> Box<dyn Broadphase<usize>>;
+
+fn bench_broadphase(c: &mut Criterion) {
+  let mut group = c.benchmark_group("broadphase_tick");
+
+  for n in [1000, 2000, 4000] {
+    let implementations: [(&str, MakeBroadphase); 2] = [
+      ("bvh", || Box::new(BvhBroadphase::new())),
+      ("sweep_and_prune", || Box::new(SweepAndPrune::new())),
+    ];
+
+    for (name, make) in implementations {
+      let mut workload = Workload::new(n);
+      let mut broadphase = make();
+      workload.populate(broadphase.as_mut());
+      let mut pairs = vec![];
+
+      group.bench_with_input(BenchmarkId::new(name, n), &n, |b, _| {
+        b.iter(|| workload.tick(broadphase.as_mut(), &mut pairs));
+      });
+    }
+  }
+
+  group.finish();
+}
+
+criterion_group!(benches, bench_broadphase);
+criterion_main!(benches);
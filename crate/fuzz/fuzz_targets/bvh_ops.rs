@@ -0,0 +1,19 @@
+//! Drives the dynamic AABB tree with operation sequences decoded from the
+//! fuzzer input, checking every query against a brute-force linear scan.
+//!
+//! Run with `cargo fuzz run bvh_ops` from the `crate` directory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../tests/support/bvh_ops.rs"]
+mod bvh_ops;
+
+fuzz_target!(|data: &[u8]| {
+  let mut bytes = data;
+  let mut harness = bvh_ops::Harness::new();
+  while let Some(op) = bvh_ops::decode_op(&mut bytes) {
+    harness.apply(&op);
+  }
+});
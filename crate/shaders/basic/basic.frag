@@ -1,7 +1,7 @@
 #version 300 es
 precision mediump float;
 
-// uniform vec3 u_color;
+uniform vec4 u_color;
 // uniform float u_time;
 
 // in vec2 f_position;
@@ -13,5 +13,5 @@ void main() {
   // float g = abs(2 * fract(f_position.y) - 1.0);
   // color = vec4(u_color.r, g, 1.0, 1.0);
 
-  color = vec4(0.2f, 0.25f, 0.3f, 0.4f);
+  color = u_color;
 }
\ No newline at end of file
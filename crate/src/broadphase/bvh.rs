@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+
+use crate::broadphase::Broadphase;
+use crate::bvh::aabb::{NodeIdx, Tree, AABB};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Broadphase backed by the dynamic AABB tree.  Proxies are stored with
+/// fattened volumes, so reported pairs may be slightly conservative.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+  serialize = "D: Serialize + Eq + Hash",
+  deserialize = "D: Deserialize<'de> + Eq + Hash"
+))]
+pub struct BvhBroadphase<D> {
+  pub tree: Tree<D>,
+  /// handle of the tree leaf belonging to each proxy
+  handles: HashMap<D, NodeIdx>
+}
+
+impl<D> BvhBroadphase<D> {
+  pub fn new() -> Self {
+    Self::with_tree(Tree::new())
+  }
+
+  /// wraps an empty tree, e.g. one created with a custom margin
+  pub fn with_tree(tree: Tree<D>) -> Self {
+    BvhBroadphase { tree, handles: HashMap::new() }
+  }
+}
+
+impl<D: Copy + Eq + Hash> Broadphase<D> for BvhBroadphase<D> {
+  fn insert(&mut self, data: D, volume: AABB) {
+    let handle = self.tree.insert_leaf(volume, data);
+    if let Some(old_handle) = self.handles.insert(data, handle) {
+      self.tree.remove_leaf(old_handle);
+    }
+  }
+
+  fn remove(&mut self, data: D) -> bool {
+    match self.handles.remove(&data) {
+      Some(handle) => self.tree.remove_leaf(handle).is_some(),
+      None => false
+    }
+  }
+
+  fn update(&mut self, data: D, volume: AABB, displacement: Vector2<f32>) {
+    if let Some(&handle) = self.handles.get(&data) {
+      self.tree.move_leaf(handle, volume, displacement);
+    }
+  }
+
+  fn contains(&self, data: &D) -> bool {
+    self.handles.contains_key(data)
+  }
+
+  fn len(&self) -> usize {
+    self.handles.len()
+  }
+
+  fn for_each_pair(&mut self, callback: &mut dyn FnMut(D, D)) {
+    self.tree.for_each_overlapping_pair(|_, &a, _, &b| callback(a, b));
+  }
+
+  /// internal nodes of the tree
+  fn for_each_debug_volume(&self, callback: &mut dyn FnMut(&AABB, usize)) {
+    self.tree.for_each_internal_node(|_, volume, depth| callback(volume, depth));
+  }
+}
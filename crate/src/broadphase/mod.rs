@@ -0,0 +1,46 @@
+pub mod bvh;
+pub mod spatial_hash;
+pub mod sweep_and_prune;
+
+pub use bvh::BvhBroadphase;
+pub use spatial_hash::SpatialHash;
+pub use sweep_and_prune::SweepAndPrune;
+
+use nalgebra::Vector2;
+
+use crate::bvh::aabb::AABB;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A broadphase tracks the volume of every proxy and reports pairs of proxies
+/// whose volumes may overlap.  Proxies are identified by their user data, e.g.
+/// the `Entity` owning a collider, so that scenes can swap implementations.
+pub trait Broadphase<D> {
+  /// starts tracking `data` with the given volume, replacing any previous proxy
+  fn insert(&mut self, data: D, volume: AABB);
+
+  /// stops tracking `data`, returning false if it was not tracked
+  fn remove(&mut self, data: D) -> bool;
+
+  /// Updates the volume of `data`, which is expected to move by `displacement`
+  /// during the next step.  Untracked data is ignored.
+  fn update(&mut self, data: D, volume: AABB, displacement: Vector2<f32>);
+
+  /// whether `data` is currently tracked
+  fn contains(&self, data: &D) -> bool;
+
+  /// number of tracked proxies
+  fn len(&self) -> usize;
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Invokes the callback once for every unordered pair of distinct proxies
+  /// whose volumes may overlap.  The order of the pair is unspecified.
+  fn for_each_pair(&mut self, callback: &mut dyn FnMut(D, D));
+
+  /// Invokes the callback with the volumes of the acceleration structure
+  /// itself, along with their depth in any hierarchy, for debug rendering.
+  fn for_each_debug_volume(&self, _callback: &mut dyn FnMut(&AABB, usize)) {}
+}
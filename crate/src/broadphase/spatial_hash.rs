@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use nalgebra::Vector2;
+
+use crate::broadphase::Broadphase;
+use crate::bvh::aabb::AABB;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Uniform grid broadphase for a periodic (toroidal) world, where leaving one
+/// side of the world re-enters from the opposite side.  Volumes need not lie
+/// within the world: they are wrapped around it, so a proxy straddling an edge
+/// is stored in cells on both sides and is paired with proxies across the seam.
+pub struct SpatialHash<D> {
+  /// one period of the world in each axis
+  world: AABB,
+  /// number of cells in each axis
+  dims: [usize; 2],
+  /// indices of the proxies overlapping each cell, in row-major order
+  cells: Vec<Vec<usize>>,
+  /// proxy slots, reused after removal
+  proxies: Vec<Option<Proxy<D>>>,
+  free: Vec<usize>,
+  /// slot of each proxy
+  index: HashMap<D, usize>
+}
+
+struct Proxy<D> {
+  data: D,
+  volume: AABB,
+  /// unwrapped range of cells covered by the volume
+  cells: CellRange
+}
+
+/// inclusive range of cells, before wrapping around the world
+#[derive(Clone, Copy, PartialEq)]
+struct CellRange {
+  lower: [i64; 2],
+  upper: [i64; 2]
+}
+
+impl<D> SpatialHash<D> {
+  /// Creates an empty grid over one period of the world.  The cell size is
+  /// rounded down so that a whole number of cells fits in each axis.
+  pub fn new(world: AABB, cell_size: f32) -> Self {
+    assert!(world.is_valid() && cell_size > 0.0);
+
+    let size = world.upper_bound - world.lower_bound;
+    let dims = [
+      ((size.x / cell_size).ceil() as usize).max(1),
+      ((size.y / cell_size).ceil() as usize).max(1)
+    ];
+
+    SpatialHash {
+      world,
+      dims,
+      cells: (0..dims[0] * dims[1]).map(|_| vec![]).collect(),
+      proxies: vec![],
+      free: vec![],
+      index: HashMap::new()
+    }
+  }
+
+  fn period(&self) -> Vector2<f32> {
+    self.world.upper_bound - self.world.lower_bound
+  }
+
+  fn cell_range(&self, volume: &AABB) -> CellRange {
+    let period = self.period();
+    let cell = |p: Vector2<f32>, axis: usize| {
+      let t = (p[axis] - self.world.lower_bound[axis]) / period[axis];
+      (t * self.dims[axis] as f32).floor() as i64
+    };
+
+    let mut range = CellRange {
+      lower: [cell(volume.lower_bound, 0), cell(volume.lower_bound, 1)],
+      upper: [cell(volume.upper_bound, 0), cell(volume.upper_bound, 1)]
+    };
+
+    // a volume wider than the world covers every cell in that axis once
+    for axis in 0..2 {
+      let max_upper = range.lower[axis] + self.dims[axis] as i64 - 1;
+      range.upper[axis] = range.upper[axis].min(max_upper);
+    }
+
+    range
+  }
+
+  /// visits the wrapped index of every cell in the range
+  fn for_each_cell<F: FnMut(usize)>(&self, range: CellRange, mut f: F) {
+    for y in range.lower[1]..=range.upper[1] {
+      let y = y.rem_euclid(self.dims[1] as i64) as usize;
+      for x in range.lower[0]..=range.upper[0] {
+        let x = x.rem_euclid(self.dims[0] as i64) as usize;
+        f(y * self.dims[0] + x);
+      }
+    }
+  }
+
+  fn add_to_cells(&mut self, slot: usize, range: CellRange) {
+    let mut cells = std::mem::take(&mut self.cells);
+    self.for_each_cell(range, |cell| cells[cell].push(slot));
+    self.cells = cells;
+  }
+
+  fn remove_from_cells(&mut self, slot: usize, range: CellRange) {
+    let mut cells = std::mem::take(&mut self.cells);
+    self.for_each_cell(range, |cell| cells[cell].retain(|&s| s != slot));
+    self.cells = cells;
+  }
+}
+
+/// Returns true if the volumes overlap once `b` is shifted by whole periods
+/// of the world to the copy nearest to `a` (the minimum image convention).
+pub fn periodic_overlap(a: &AABB, b: &AABB, period: Vector2<f32>) -> bool {
+  let delta = b.center() - a.center();
+  let reach = a.extents() + b.extents();
+
+  (0..2).all(|axis| {
+    let wrapped = delta[axis] - period[axis] * (delta[axis] / period[axis]).round();
+    wrapped.abs() <= reach[axis]
+  })
+}
+
+impl<D: Copy + Eq + Hash> Broadphase<D> for SpatialHash<D> {
+  fn insert(&mut self, data: D, volume: AABB) {
+    self.remove(data);
+
+    let cells = self.cell_range(&volume);
+    let proxy = Some(Proxy { data, volume, cells });
+    let slot = match self.free.pop() {
+      Some(slot) => { self.proxies[slot] = proxy; slot }
+      None => { self.proxies.push(proxy); self.proxies.len() - 1 }
+    };
+
+    self.index.insert(data, slot);
+    self.add_to_cells(slot, cells);
+  }
+
+  fn remove(&mut self, data: D) -> bool {
+    let Some(slot) = self.index.remove(&data) else { return false; };
+    let proxy = self.proxies[slot].take().unwrap();
+    self.remove_from_cells(slot, proxy.cells);
+    self.free.push(slot);
+    true
+  }
+
+  fn update(&mut self, data: D, volume: AABB, _displacement: Vector2<f32>) {
+    let Some(&slot) = self.index.get(&data) else { return; };
+
+    let new_cells = self.cell_range(&volume);
+    let proxy = self.proxies[slot].as_mut().unwrap();
+    let old_cells = proxy.cells;
+    proxy.volume = volume;
+    proxy.cells = new_cells;
+
+    // only touch the grid when the proxy crosses into different cells
+    if old_cells != new_cells {
+      self.remove_from_cells(slot, old_cells);
+      self.add_to_cells(slot, new_cells);
+    }
+  }
+
+  fn contains(&self, data: &D) -> bool {
+    self.index.contains_key(data)
+  }
+
+  fn len(&self) -> usize {
+    self.index.len()
+  }
+
+  fn for_each_pair(&mut self, callback: &mut dyn FnMut(D, D)) {
+    let period = self.period();
+
+    // proxies may share several cells, so each pair is reported only once
+    let mut seen = HashSet::new();
+
+    for cell in &self.cells {
+      for (i, &slot_a) in cell.iter().enumerate() {
+        for &slot_b in &cell[i + 1..] {
+          let key = (slot_a.min(slot_b), slot_a.max(slot_b));
+          if !seen.insert(key) { continue; }
+
+          let a = self.proxies[slot_a].as_ref().unwrap();
+          let b = self.proxies[slot_b].as_ref().unwrap();
+          if periodic_overlap(&a.volume, &b.volume, period) {
+            callback(a.data, b.data);
+          }
+        }
+      }
+    }
+  }
+
+  /// occupied cells of the grid
+  fn for_each_debug_volume(&self, callback: &mut dyn FnMut(&AABB, usize)) {
+    let cell_size = self.period().component_div(&Vector2::new(self.dims[0] as f32, self.dims[1] as f32));
+
+    for (i, cell) in self.cells.iter().enumerate() {
+      if cell.is_empty() { continue; }
+      let coords = Vector2::new((i % self.dims[0]) as f32, (i / self.dims[0]) as f32);
+      let lower = self.world.lower_bound + coords.component_mul(&cell_size);
+      callback(&AABB::new(lower, lower + cell_size), 0);
+    }
+  }
+}
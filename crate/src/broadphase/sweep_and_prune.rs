@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use nalgebra::Vector2;
+
+use crate::broadphase::Broadphase;
+use crate::bvh::aabb::AABB;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Incremental sort-and-sweep broadphase.  Proxies are kept sorted by the
+/// lower bound of their volume along the x-axis.  Since bodies move only a
+/// little between steps, the list stays nearly sorted, and insertion sort
+/// restores the order in close to linear time.  A sweep along the sorted
+/// list then only compares proxies whose x-intervals overlap.
+pub struct SweepAndPrune<D> {
+  /// proxies, sorted by `volume.lower_bound.x` whenever `sorted` is true
+  proxies: Vec<(AABB, D)>,
+  /// position of each proxy within `proxies`
+  index: HashMap<D, usize>,
+  sorted: bool
+}
+
+impl<D> SweepAndPrune<D> {
+  pub fn new() -> Self {
+    SweepAndPrune { proxies: vec![], index: HashMap::new(), sorted: true }
+  }
+}
+
+impl<D: Copy + Eq + Hash> SweepAndPrune<D> {
+  /// restores the order of the proxies with an insertion sort
+  fn sort(&mut self) {
+    if self.sorted { return; }
+
+    for i in 1..self.proxies.len() {
+      let mut j = i;
+      while j > 0 && self.proxies[j - 1].0.lower_bound.x > self.proxies[j].0.lower_bound.x {
+        self.proxies.swap(j - 1, j);
+        self.index.insert(self.proxies[j].1, j);
+        j -= 1;
+      }
+      self.index.insert(self.proxies[j].1, j);
+    }
+
+    self.sorted = true;
+  }
+}
+
+impl<D: Copy + Eq + Hash> Broadphase<D> for SweepAndPrune<D> {
+  fn insert(&mut self, data: D, volume: AABB) {
+    match self.index.get(&data) {
+      Some(&i) => { self.proxies[i].0 = volume; }
+      None => {
+        self.index.insert(data, self.proxies.len());
+        self.proxies.push((volume, data));
+      }
+    }
+    self.sorted = false;
+  }
+
+  fn remove(&mut self, data: D) -> bool {
+    let Some(i) = self.index.remove(&data) else { return false; };
+
+    // swapping in the last proxy keeps removal constant time; the order it
+    // breaks is restored by the insertion sort before the next sweep
+    self.proxies.swap_remove(i);
+    if let Some(&(_, moved)) = self.proxies.get(i) {
+      self.index.insert(moved, i);
+      self.sorted = false;
+    }
+
+    true
+  }
+
+  fn update(&mut self, data: D, volume: AABB, _displacement: Vector2<f32>) {
+    if let Some(&i) = self.index.get(&data) {
+      self.proxies[i].0 = volume;
+      self.sorted = false;
+    }
+  }
+
+  fn contains(&self, data: &D) -> bool {
+    self.index.contains_key(data)
+  }
+
+  fn len(&self) -> usize {
+    self.proxies.len()
+  }
+
+  fn for_each_pair(&mut self, callback: &mut dyn FnMut(D, D)) {
+    self.sort();
+
+    for (i, (volume_a, data_a)) in self.proxies.iter().enumerate() {
+      // later proxies start no earlier along x, so stop
+      // at the first one which starts after this one ends
+      for (volume_b, data_b) in &self.proxies[i + 1..] {
+        if volume_b.lower_bound.x > volume_a.upper_bound.x { break; }
+        if volume_a.overlaps(volume_b) { callback(*data_a, *data_b); }
+      }
+    }
+  }
+}
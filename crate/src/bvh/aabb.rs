@@ -1,52 +1,243 @@
-use nalgebra::{self, Vector2};
+use nalgebra::{self, RealField, SVector};
+use serde::{Deserialize, Serialize};
 use slotmap::{new_key_type, SlotMap};
 use std::collections::BinaryHeap;
 
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct AABB {
+/// Scalar types usable as coordinates of a volume, i.e. `f32` or `f64`.
+pub trait Real: RealField + Copy {}
+impl<T: RealField + Copy> Real for T {}
+
+/// converts a constant to the scalar type of a volume
+fn scalar<T: Real>(x: f64) -> T {
+  nalgebra::convert(x)
+}
+
+/// converts a scalar to `f64`, which is lossless for `f32` and `f64`
+fn to_f64<T: Real>(x: T) -> f64 {
+  nalgebra::convert_unchecked(x)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// An axis-aligned bounding box in `N` dimensions with coordinates of type `T`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Real + Serialize", deserialize = "T: Real + Deserialize<'de>"))]
+pub struct AABBN<T, const N: usize> {
   /// lower bound in each axis
-  pub lower_bound: nalgebra::Vector2<f32>,
+  pub lower_bound: SVector<T, N>,
   /// upper bound in each axis
-  pub upper_bound: nalgebra::Vector2<f32>
+  pub upper_bound: SVector<T, N>
 }
 
-impl AABB {
+/// The 2D single-precision volume used throughout the engine.
+pub type AABB = AABBN<f32, 2>;
+
+/// A 3D single-precision volume.
+pub type AABB3 = AABBN<f32, 3>;
+
+impl<T: Real, const N: usize> AABBN<T, N> {
+  pub fn new(lower_bound: SVector<T, N>, upper_bound: SVector<T, N>) -> Self {
+    return AABBN { lower_bound, upper_bound };
+  }
+
+  /// returns the smallest volume containing every point, or `None` if empty
+  pub fn from_points<I>(points: I) -> Option<Self>
+  where I: IntoIterator<Item = SVector<T, N>> {
+    let mut points = points.into_iter();
+    let first = points.next()?;
+
+    let mut result = AABBN { lower_bound: first, upper_bound: first };
+    for point in points {
+      result.lower_bound = result.lower_bound.inf(&point);
+      result.upper_bound = result.upper_bound.sup(&point);
+    }
+
+    return Some(result);
+  }
+
+  /// returns true if the bounds are finite and correctly ordered in each axis
+  pub fn is_valid(&self) -> bool {
+    return (0..N).all(|i| self.lower_bound[i] <= self.upper_bound[i])
+        && self.lower_bound.iter().all(|v| v.is_finite())
+        && self.upper_bound.iter().all(|v| v.is_finite());
+  }
+
   // TODO (Ben @ 2024/08/20) make efficient with SIMD
-  pub fn join(a: &AABB, b: &AABB) -> AABB { 
-    let lower_bound = nalgebra::Vector2::inf(&a.lower_bound, &b.lower_bound);
-    let upper_bound = nalgebra::Vector2::inf(&a.upper_bound, &b.upper_bound);
-    return AABB { lower_bound, upper_bound };
+  /// returns the smallest volume containing both `a` and `b`
+  pub fn join(a: &Self, b: &Self) -> Self {
+    let lower_bound = a.lower_bound.inf(&b.lower_bound);
+    let upper_bound = a.upper_bound.sup(&b.upper_bound);
+    return AABBN { lower_bound, upper_bound };
+  }
+
+  /// returns the volume shared by `a` and `b`, or `None` if they are disjoint
+  pub fn intersection(a: &Self, b: &Self) -> Option<Self> {
+    let lower_bound = a.lower_bound.sup(&b.lower_bound);
+    let upper_bound = a.upper_bound.inf(&b.upper_bound);
+    let result = AABBN { lower_bound, upper_bound };
+    return if result.is_valid() { Some(result) } else { None };
   }
 
-  pub fn surface_area(&self) -> f32 {
+  /// Returns the measure of the boundary of this volume, i.e. the perimeter in
+  /// 2D and the surface area in 3D.  This is the cost metric of the surface
+  /// area heuristic (SAH) used by `Tree`.
+  pub fn sah_measure(&self) -> T {
     let diffs = self.upper_bound - self.lower_bound;
-    return 2.0 * (diffs.x + diffs.y);
+
+    // each pair of opposite faces has the product of the other extents
+    let mut total = T::zero();
+    for i in 0..N {
+      total += (0..N).filter(|&j| j != i).fold(T::one(), |acc, j| acc * diffs[j]);
+    }
+    return scalar::<T>(2.0) * total;
+  }
+
+  /// returns the length, area or volume enclosed, depending on the dimension
+  pub fn volume(&self) -> T {
+    return (self.upper_bound - self.lower_bound).product();
+  }
+
+  /// returns true if this volume and `other` intersect
+  pub fn overlaps(&self, other: &Self) -> bool {
+    return (0..N).all(|i| {
+      self.lower_bound[i] <= other.upper_bound[i] && other.lower_bound[i] <= self.upper_bound[i]
+    });
+  }
+
+  /// returns true if `other` lies entirely within this volume
+  pub fn contains(&self, other: &Self) -> bool {
+    return (0..N).all(|i| {
+      self.lower_bound[i] <= other.lower_bound[i] && other.upper_bound[i] <= self.upper_bound[i]
+    });
   }
 
-  pub fn ray_cast(&self, p1: Vector2<f32>, p2: Vector2<f32>) -> bool {
-    // TODO (Ben @ 2024/08/25) ray cast volumes
-    panic!("not implemented");
-  } 
+  /// returns true if `point` lies within this volume
+  pub fn contains_point(&self, point: SVector<T, N>) -> bool {
+    return (0..N).all(|i| self.lower_bound[i] <= point[i] && point[i] <= self.upper_bound[i]);
+  }
+
+  /// returns the center of this volume
+  pub fn center(&self) -> SVector<T, N> {
+    return (self.lower_bound + self.upper_bound) * scalar::<T>(0.5);
+  }
+
+  /// returns the half-widths of this volume in each axis
+  pub fn extents(&self) -> SVector<T, N> {
+    return (self.upper_bound - self.lower_bound) * scalar::<T>(0.5);
+  }
+
+  /// returns the distance from `point` to the closest point
+  /// of this volume, which is zero if the point is inside
+  pub fn distance_to_point(&self, point: SVector<T, N>) -> T {
+    let below = self.lower_bound - point;
+    let above = point - self.upper_bound;
+    return below.sup(&above).sup(&SVector::zeros()).norm();
+  }
+
+  /// returns a copy of this volume enlarged by `margin` in every direction
+  pub fn expand_by_margin(&self, margin: T) -> Self {
+    let r = SVector::repeat(margin);
+    return AABBN {
+      lower_bound : self.lower_bound - r,
+      upper_bound : self.upper_bound + r
+    };
+  }
+
+  /// returns a copy of this volume shifted by `offset`
+  pub fn translate(&self, offset: SVector<T, N>) -> Self {
+    return AABBN {
+      lower_bound : self.lower_bound + offset,
+      upper_bound : self.upper_bound + offset
+    };
+  }
+
+  /// Intersects the segment from `p1` to `p2` with this volume using the slab
+  /// method, returning the fraction along the segment at which it enters the
+  /// volume (zero if `p1` is inside), or `None` if the segment misses.
+  pub fn ray_cast(&self, p1: SVector<T, N>, p2: SVector<T, N>) -> Option<T> {
+    let d = p2 - p1;
+    let mut t_min = T::zero();
+    let mut t_max = T::one();
+
+    for i in 0..N {
+      let (lower, upper) = (self.lower_bound[i], self.upper_bound[i]);
+
+      if d[i].abs() < T::default_epsilon() {
+        // segment is parallel to this slab
+        if p1[i] < lower || upper < p1[i] { return None; }
+      } else {
+        // intersect segment with the near and far planes of this slab
+        let inv_d = T::one() / d[i];
+        let mut t1 = (lower - p1[i]) * inv_d;
+        let mut t2 = (upper - p1[i]) * inv_d;
+        if t1 > t2 { std::mem::swap(&mut t1, &mut t2); }
+
+        t_min = t_min.max(t1);
+        t_max = t_max.min(t2);
+        if t_min > t_max { return None; }
+      }
+    }
+
+    return Some(t_min);
+  }
+
+  /// Sweeps `moving` along `displacement`, returning the fraction of the
+  /// displacement at which it first touches this volume (zero if they already
+  /// overlap), or `None` if they never touch.  Equivalent to casting the
+  /// center of `moving` against this volume grown by its extents.
+  pub fn box_cast(&self, moving: &Self, displacement: SVector<T, N>) -> Option<T> {
+    let p1 = moving.center();
+    return self.minkowski_sum(moving.extents()).ray_cast(p1, p1 + displacement);
+  }
+
+  /// returns a copy of this volume grown by `extents` along each axis
+  fn minkowski_sum(&self, extents: SVector<T, N>) -> Self {
+    return AABBN {
+      lower_bound : self.lower_bound - extents,
+      upper_bound : self.upper_bound + extents
+    };
+  }
+}
+
+impl<T: Real> AABBN<T, 2> {
+  /// returns the perimeter of this volume, i.e. its `sah_measure` in 2D
+  pub fn perimeter(&self) -> T {
+    return self.sah_measure();
+  }
+
+  /// returns the area enclosed by this volume, i.e. its `volume` in 2D
+  pub fn area(&self) -> T {
+    return self.volume();
+  }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
 // slotmap provides a macro for defining the index type
 new_key_type! {
-  struct NodeIdx;
+  /// Stable handle to a node of a `Tree`.  The handle returned by
+  /// `Tree::insert_leaf` remains valid until the leaf is removed.
+  pub struct NodeIdx;
 }
 
 /// A `Node` is either `NodeKind::Leaf` or `NodeKind::Internal`.  Regardless of
 /// the kind, each node is annotated with:
 /// - A reference to its parent, or `None` for the root.
 /// - An axis-aligned bounding box (AABB).
-pub struct Node<D> {
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+  serialize = "D: Serialize, T: Real + Serialize",
+  deserialize = "D: Deserialize<'de>, T: Real + Deserialize<'de>"
+))]
+pub struct Node<D, T, const N: usize> {
   parent : Option<NodeIdx>,
-  volume : AABB,
+  volume : AABBN<T, N>,
   kind   : NodeKind<D>,
 }
 
+#[derive(Serialize, Deserialize)]
 pub enum NodeKind<D> {
   /// An `Internal` node stores the index of its left and right children.
   Internal {
@@ -59,63 +250,377 @@ pub enum NodeKind<D> {
   }
 }
 
-pub struct Tree<D> {
-  nodes    : SlotMap<NodeIdx, Node<D>>,
-  root_idx : Option<NodeIdx>
+/// Default margin by which leaf volumes are fattened, in world units.
+pub const DEFAULT_MARGIN: f32 = 0.01;
+
+/// Default multiplier applied to a leaf's predicted displacement when
+/// fattening its volume in the direction of motion.
+pub const DEFAULT_DISPLACEMENT_MULTIPLIER: f32 = 4.0;
+
+/// A dynamic bounding volume hierarchy over `N`-dimensional volumes with
+/// coordinates of type `T`, storing user data `D` in its leaves.
+///
+/// The tree serializes its exact structure, including leaf handles, so a
+/// restored snapshot has the same shape and reports pairs in the same order.
+/// Handles allocated afterwards may differ from those the original tree would
+/// have allocated, but no query or update depends on handle values, so the
+/// restored tree evolves exactly like the original.  Call `validate` after
+/// deserializing untrusted data.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+  serialize = "D: Serialize, T: Real + Serialize",
+  deserialize = "D: Deserialize<'de>, T: Real + Deserialize<'de>"
+))]
+pub struct TreeN<D, T, const N: usize> {
+  nodes    : SlotMap<NodeIdx, Node<D, T, N>>,
+  root_idx : Option<NodeIdx>,
+  /// leaf volumes are enlarged by this margin in each direction
+  margin : T,
+  /// leaf volumes are enlarged by this multiple of their displacement
+  displacement_multiplier : T
 }
 
+/// The 2D single-precision tree used throughout the engine.
+pub type Tree<D> = TreeN<D, f32, 2>;
+
 ////////////////////////////////////////////////////////////////////////////////
 
-impl<D> Tree<D> {
+impl<D, T: Real, const N: usize> TreeN<D, T, N> {
   pub fn new() -> Self {
-    return Tree {
+    return TreeN::with_margin(
+      scalar(DEFAULT_MARGIN.into()),
+      scalar(DEFAULT_DISPLACEMENT_MULTIPLIER.into())
+    );
+  }
+
+  /// Creates an empty tree whose leaf volumes are fattened by `margin`, plus
+  /// `displacement_multiplier` times the displacement passed to `move_leaf`.
+  pub fn with_margin(margin: T, displacement_multiplier: T) -> Self {
+    return TreeN {
       nodes : SlotMap::default(),
-      root_idx : None
-    }
-  }
-
-  pub fn ray_cast(&self, p1: Vector2<f32>, p2: Vector2<f32>) -> bool {
-    match self.root_idx {
-      None => { return false; }
-      Some(root_idx) => {
-        // list of boxes to check for collisions
-        let mut stack = vec![root_idx];
-
-        while let Some(top_idx) = stack.pop() {
-          if let Some(node) = self.nodes.get(top_idx) {
-            // exit early if ray does not intersect this level
-            if !node.volume.ray_cast(p1, p2) { continue; }
-
-            match node.kind {
-              // perform narrow phase for leaf nodes
-              NodeKind::Leaf { .. } => {
-                return true;
-              }
-              // ray cast children of internal nodes
-              NodeKind::Internal { child1, child2 } => {
-                stack.push(child1);
-                stack.push(child2);
-              }
-            }
-          } else {
-            panic!("invalid index");
+      root_idx : None,
+      margin,
+      displacement_multiplier
+    }
+  }
+
+  /// Casts the segment from `p1` to `p1 + max_fraction * (p2 - p1)` against
+  /// the tree.  The callback is invoked with each leaf whose volume the segment
+  /// enters, along with the entry fraction, and controls the query:
+  /// - return `0.0` to terminate the ray cast
+  /// - return a negative value to ignore this leaf and continue
+  /// - return a fraction to clip the segment, e.g. to find the closest hit
+  ///
+  /// Leaves are not visited in any particular order.
+  pub fn ray_cast<F>(
+    &self,
+    p1: SVector<T, N>,
+    p2: SVector<T, N>,
+    max_fraction: T,
+    callback: F
+  ) where F: FnMut(NodeIdx, &D, T) -> T {
+    self.cast(p1, p2, max_fraction, SVector::zeros(), callback);
+  }
+
+  /// Sweeps `volume` along `displacement` against the tree, for continuous
+  /// collision detection of fast-moving objects.  The callback is invoked with
+  /// each leaf the swept volume touches, along with the time of impact as a
+  /// fraction of the displacement, and controls the query as for `ray_cast`.
+  pub fn box_cast<F>(&self, volume: &AABBN<T, N>, displacement: SVector<T, N>, callback: F)
+  where F: FnMut(NodeIdx, &D, T) -> T {
+    let p1 = volume.center();
+    self.cast(p1, p1 + displacement, T::one(), volume.extents(), callback);
+  }
+
+  /// casts a segment against the tree with every
+  /// node volume grown by `extents` along each axis
+  fn cast<F>(
+    &self,
+    p1: SVector<T, N>,
+    p2: SVector<T, N>,
+    max_fraction: T,
+    extents: SVector<T, N>,
+    mut callback: F
+  ) where F: FnMut(NodeIdx, &D, T) -> T {
+    let Some(root_idx) = self.root_idx else { return; };
+    let mut max_fraction = max_fraction;
+
+    // list of boxes to check for collisions
+    let mut stack = vec![root_idx];
+
+    while let Some(top_idx) = stack.pop() {
+      let node = &self.nodes[top_idx];
+
+      // exit early if the clipped ray does not intersect this level
+      let fraction = match node.volume.minkowski_sum(extents).ray_cast(p1, p2) {
+        Some(fraction) if fraction <= max_fraction => fraction,
+        _ => { continue; }
+      };
+
+      match node.kind {
+        // defer to the callback for narrow phase on leaf nodes
+        NodeKind::Leaf { ref data } => {
+          let value = callback(top_idx, data, fraction);
+          if value == T::zero() { return; }
+          if value > T::zero() { max_fraction = max_fraction.min(value); }
+        }
+        // ray cast children of internal nodes
+        NodeKind::Internal { child1, child2 } => {
+          stack.push(child1);
+          stack.push(child2);
+        }
+      }
+    }
+  }
+
+  /// Invokes the callback with each leaf whose volume overlaps `volume`.  The
+  /// callback returns `false` to terminate the query early.
+  pub fn query_aabb<F>(&self, volume: &AABBN<T, N>, callback: F)
+  where F: FnMut(NodeIdx, &D) -> bool {
+    self.query(|node_volume| node_volume.overlaps(volume), callback);
+  }
+
+  /// Invokes the callback with each leaf whose volume contains `point`.  The
+  /// callback returns `false` to terminate the query early.
+  pub fn query_point<F>(&self, point: SVector<T, N>, callback: F)
+  where F: FnMut(NodeIdx, &D) -> bool {
+    self.query(|node_volume| node_volume.contains_point(point), callback);
+  }
+
+  /// visits each leaf for which `test` passes on the leaf and all ancestors
+  fn query<Q, F>(&self, test: Q, mut callback: F)
+  where Q: Fn(&AABBN<T, N>) -> bool, F: FnMut(NodeIdx, &D) -> bool {
+    let Some(root_idx) = self.root_idx else { return; };
+
+    // list of boxes to check for collisions
+    let mut stack = vec![root_idx];
+
+    while let Some(top_idx) = stack.pop() {
+      let node = &self.nodes[top_idx];
+
+      // exit early if the query does not intersect this level
+      if !test(&node.volume) { continue; }
+
+      match node.kind {
+        NodeKind::Leaf { ref data } => {
+          if !callback(top_idx, data) { return; }
+        }
+        NodeKind::Internal { child1, child2 } => {
+          stack.push(child1);
+          stack.push(child2);
+        }
+      }
+    }
+  }
+
+  /// Returns the leaf closest to `point` along with its distance, or `None` if
+  /// no leaf lies within `max_dist`.  Distances are measured to the stored
+  /// (fattened) leaf volumes; use `nearest_by` for exact distances.
+  pub fn nearest(&self, point: SVector<T, N>, max_dist: T) -> Option<(NodeIdx, &D, T)> {
+    return self.nearest_by(point, max_dist, |_, _, bound| bound);
+  }
+
+  /// Returns up to `k` leaves closest to `point`, ordered by increasing
+  /// distance to their stored (fattened) volumes.
+  pub fn k_nearest(&self, point: SVector<T, N>, k: usize) -> Vec<(NodeIdx, &D, T)> {
+    return self.k_nearest_by(point, k, |_, _, bound| bound);
+  }
+
+  /// Like `nearest`, but the distance to each candidate leaf is refined by the
+  /// callback, which is invoked with the leaf and the distance to its volume.
+  /// The callback must return a distance no smaller than the one it is given,
+  /// e.g. the distance to the shape inside the volume, or infinity to ignore
+  /// the leaf.  Leaves which cannot be closer are never refined.
+  pub fn nearest_by<F>(&self, point: SVector<T, N>, max_dist: T, distance: F) -> Option<(NodeIdx, &D, T)>
+  where F: FnMut(NodeIdx, &D, T) -> T {
+    return self.best_first(point, max_dist, 1, distance).pop();
+  }
+
+  /// Like `k_nearest`, but with distances refined by the callback as
+  /// described for `nearest_by`.
+  pub fn k_nearest_by<F>(&self, point: SVector<T, N>, k: usize, distance: F) -> Vec<(NodeIdx, &D, T)>
+  where F: FnMut(NodeIdx, &D, T) -> T {
+    return self.best_first(point, scalar(f64::INFINITY), k, distance);
+  }
+
+  /// Visits nodes in order of increasing distance to their volume, which is a
+  /// lower bound on the distance to any leaf beneath them.  Leaves are queued
+  /// a second time with their refined distance, and reported when popped.
+  fn best_first<F>(&self, point: SVector<T, N>, max_dist: T, k: usize, mut distance: F) -> Vec<(NodeIdx, &D, T)>
+  where F: FnMut(NodeIdx, &D, T) -> T {
+    let mut result = vec![];
+    let Some(root_idx) = self.root_idx else { return result; };
+    if k == 0 { return result; }
+
+    let mut priority_queue = BinaryHeap::new();
+    let root_dist = self.nodes[root_idx].volume.distance_to_point(point);
+    priority_queue.push(Neighbour::new(root_dist, false, 0, root_idx));
+    let mut num_queued = 1;
+
+    while let Some(current) = priority_queue.pop() {
+      // everything left in the queue is at least this far away
+      let dist = scalar::<T>(current.dist.0.0);
+      if dist > max_dist || !dist.is_finite() { break; }
+
+      match self.nodes[current.idx].kind {
+        NodeKind::Leaf { ref data } if current.refined => {
+          result.push((current.idx, data, dist));
+          if result.len() == k { break; }
+        }
+        NodeKind::Leaf { ref data } => {
+          let refined = distance(current.idx, data, dist);
+          priority_queue.push(Neighbour::new(refined, true, num_queued, current.idx));
+          num_queued += 1;
+        }
+        NodeKind::Internal { child1, child2 } => {
+          for child in [child1, child2] {
+            let child_dist = self.nodes[child].volume.distance_to_point(point);
+            priority_queue.push(Neighbour::new(child_dist, false, num_queued, child));
+            num_queued += 1;
           }
         }
+      }
+    }
+
+    result
+  }
+
+  /// Invokes the callback once for every unordered pair of distinct leaves
+  /// whose volumes overlap, by descending the tree against itself.
+  pub fn for_each_overlapping_pair<F>(&self, mut callback: F)
+  where F: FnMut(NodeIdx, &D, NodeIdx, &D) {
+    let Some(root_idx) = self.root_idx else { return; };
+
+    // pairs of subtrees to check for overlaps, where a pair (n, n)
+    // stands for all pairs of distinct leaves within the subtree n
+    let mut stack = vec![(root_idx, root_idx)];
+
+    while let Some((idx_a, idx_b)) = stack.pop() {
+      let node_a = &self.nodes[idx_a];
+      let node_b = &self.nodes[idx_b];
 
-        return false;
+      if idx_a == idx_b {
+        // leaves never overlap with themselves
+        if let NodeKind::Internal { child1, child2 } = node_a.kind {
+          stack.push((child1, child1));
+          stack.push((child2, child2));
+          stack.push((child1, child2));
+        }
+        continue;
+      }
+
+      // exit early if the subtrees are disjoint
+      if !node_a.volume.overlaps(&node_b.volume) { continue; }
+
+      match (&node_a.kind, &node_b.kind) {
+        (NodeKind::Leaf { data: data_a }, NodeKind::Leaf { data: data_b }) => {
+          callback(idx_a, data_a, idx_b, data_b);
+        }
+        // descend into the larger of the two internal nodes
+        (NodeKind::Internal { child1, child2 }, NodeKind::Leaf { .. }) => {
+          stack.push((*child1, idx_b));
+          stack.push((*child2, idx_b));
+        }
+        (NodeKind::Internal { child1, child2 }, NodeKind::Internal { .. })
+          if node_a.volume.sah_measure() >= node_b.volume.sah_measure() => {
+          stack.push((*child1, idx_b));
+          stack.push((*child2, idx_b));
+        }
+        (_, NodeKind::Internal { child1, child2 }) => {
+          stack.push((idx_a, *child1));
+          stack.push((idx_a, *child2));
+        }
       }
     }
   }
 
-  /// dynamic insertion
-  pub fn insert_leaf(&mut self, volume: AABB, data: D) {
+  /// dynamic insertion, returning a handle to the new leaf.  The stored
+  /// volume is fattened by the tree's margin so small motions are cheap.
+  pub fn insert_leaf(&mut self, volume: AABBN<T, N>, data: D) -> NodeIdx {
     // create new leaf
     let leaf_idx: NodeIdx = self.nodes.insert(Node {
-      volume,
+      volume : self.fatten(&volume, SVector::zeros()),
       parent : None,
       kind   : NodeKind::Leaf { data }
     });
 
+    self.attach_leaf(leaf_idx);
+    self.debug_validate();
+    leaf_idx
+  }
+
+  /// dynamic removal, returning the data stored in the leaf, or `None` if
+  /// the handle does not refer to a leaf of this tree
+  pub fn remove_leaf(&mut self, leaf_idx: NodeIdx) -> Option<D> {
+    if !self.is_leaf(leaf_idx) { return None; }
+
+    self.detach_leaf(leaf_idx);
+
+    let removed = self.nodes.remove(leaf_idx);
+    self.debug_validate();
+
+    match removed {
+      Some(Node { kind: NodeKind::Leaf { data }, .. }) => Some(data),
+      _ => unreachable!("leaf was checked above")
+    }
+  }
+
+  /// Updates the volume of a leaf which has moved to `volume` and is expected
+  /// to move by `displacement` next step.  The leaf is only reinserted when
+  /// `volume` escapes its fattened volume, in which case `true` is returned.
+  pub fn move_leaf(
+    &mut self,
+    leaf_idx: NodeIdx,
+    volume: AABBN<T, N>,
+    displacement: SVector<T, N>
+  ) -> bool {
+    if !self.is_leaf(leaf_idx) { return false; }
+
+    let fat_volume = self.fatten(&volume, displacement);
+    let tree_volume = &self.nodes[leaf_idx].volume;
+
+    if tree_volume.contains(&volume) {
+      // the tree volume still contains the leaf, but if the leaf has since
+      // slowed down, it may be much too large and should be shrunk
+      let huge_volume = fat_volume.expand_by_margin(self.margin * scalar(4.0));
+      if huge_volume.contains(tree_volume) { return false; }
+    }
+
+    self.detach_leaf(leaf_idx);
+    self.nodes[leaf_idx].volume = fat_volume;
+    self.attach_leaf(leaf_idx);
+    self.debug_validate();
+
+    return true;
+  }
+
+  /// returns the fattened volume stored for a leaf
+  pub fn fat_volume(&self, leaf_idx: NodeIdx) -> Option<&AABBN<T, N>> {
+    self.nodes.get(leaf_idx).map(|node| &node.volume)
+  }
+
+  fn is_leaf(&self, node_idx: NodeIdx) -> bool {
+    matches!(
+      self.nodes.get(node_idx),
+      Some(Node { kind: NodeKind::Leaf { .. }, .. })
+    )
+  }
+
+  /// expands a volume by the margin, and further in the
+  /// direction of the predicted displacement
+  fn fatten(&self, volume: &AABBN<T, N>, displacement: SVector<T, N>) -> AABBN<T, N> {
+    let mut fat = volume.expand_by_margin(self.margin);
+    let d = displacement * self.displacement_multiplier;
+
+    for i in 0..N {
+      if d[i] < T::zero() { fat.lower_bound[i] += d[i]; } else { fat.upper_bound[i] += d[i]; }
+    }
+
+    fat
+  }
+
+  /// links an existing, detached leaf into the tree
+  fn attach_leaf(&mut self, leaf_idx: NodeIdx) {
     if let Some(root_idx) = self.root_idx {
       // 1. tree is non-empty, so search for the best sibling
       // to join with the leaf under a new parent node
@@ -123,7 +628,7 @@ impl<D> Tree<D> {
       let old_parent_idx = self.nodes[sibling_idx].parent;
 
       // 2: replace sibling with new_parent, whose children are sibling and leaf
-      let new_parent_volume = AABB::join(
+      let new_parent_volume = AABBN::join(
         &self.nodes[leaf_idx].volume,
         &self.nodes[sibling_idx].volume
       );
@@ -170,10 +675,52 @@ impl<D> Tree<D> {
     }
   }
 
+  /// unlinks a leaf from the tree without removing it from the arena
+  fn detach_leaf(&mut self, leaf_idx: NodeIdx) {
+    if let Some(parent_idx) = self.nodes[leaf_idx].parent {
+      // 1. the leaf's sibling will take the place of its parent
+      let sibling_idx = match self.nodes[parent_idx].kind {
+        NodeKind::Internal { child1, child2 } => {
+          if child1 == leaf_idx { child2 } else { child1 }
+        }
+        _ => unreachable!("parent cannot be a leaf")
+      };
+
+      // 2. remove the parent, promoting the sibling
+      let grandparent_idx = self.nodes[parent_idx].parent;
+      self.nodes.remove(parent_idx);
+      self.nodes[sibling_idx].parent = grandparent_idx;
+      self.nodes[leaf_idx].parent = None;
+
+      if let Some(grandparent_idx) = grandparent_idx {
+        // parent was not the root, so replace
+        // it with sibling under grandparent
+        let grandparent = &mut self.nodes[grandparent_idx];
+        match grandparent.kind {
+          NodeKind::Internal { ref mut child1, ref mut child2 } => {
+            if *child1 == parent_idx { *child1 = sibling_idx; }
+            if *child2 == parent_idx { *child2 = sibling_idx; }
+          }
+          _ => unreachable!("grandparent cannot be a leaf")
+        }
+
+        // 3: walk back up the tree, refitting AABBs
+        self.refit_ancestors(grandparent_idx);
+      } else {
+        // parent was the root, so
+        // make sibling the root
+        self.root_idx = Some(sibling_idx);
+      }
+    } else {
+      // leaf was the root, so the tree is now empty
+      self.root_idx = None;
+    }
+  }
+
   /// refits the volume of a single node to contain its children
   fn refit_node(&mut self, node_idx: NodeIdx) {
     if let NodeKind::Internal { child1, child2 } = self.nodes[node_idx].kind {
-      let new_volume = AABB::join(
+      let new_volume = AABBN::join(
         &self.nodes[child1].volume,
         &self.nodes[child2].volume
       );
@@ -182,72 +729,604 @@ impl<D> Tree<D> {
     }
   }
 
-  /// refits the volume of a node and all its ancestors
-  fn refit_ancestors(&mut self, leaf_idx: NodeIdx) {
+  /// refits the volume of a node and all its ancestors,
+  /// rotating each ancestor to reduce the cost of the tree
+  fn refit_ancestors(&mut self, node_idx: NodeIdx) {
     // walk back up the tree, refitting AABBs
-    let mut current_idx = Some(leaf_idx);
+    let mut current_idx = Some(node_idx);
     while let Some(idx) = current_idx {
       self.refit_node(idx);
+      self.rotate(idx);
       current_idx = self.nodes[idx].parent;
     }
   }
+
+  /// Performs the tree rotation below `node_idx` which most reduces the cost of
+  /// the tree, if any, following Kopta et al. 2012, "Fast, Effective BVH Updates
+  /// for Animated Scenes".  A rotation swaps a child of the node with one of its
+  /// grandchildren, or swaps two grandchildren on opposite sides.  Since the set
+  /// of leaves below the node is unchanged, its own volume is unaffected.
+  fn rotate(&mut self, node_idx: NodeIdx) {
+    let NodeKind::Internal { child1: b, child2: c } = self.nodes[node_idx].kind else {
+      return;
+    };
+
+    let measure = |tree: &Self, x: NodeIdx| tree.nodes[x].volume.sah_measure();
+    let join_measure = |tree: &Self, x: NodeIdx, y: NodeIdx| {
+      AABBN::join(&tree.nodes[x].volume, &tree.nodes[y].volume).sah_measure()
+    };
+
+    // best rotation so far, as (cost change, first node, second node)
+    let mut best: Option<(T, NodeIdx, NodeIdx)> = None;
+    let mut consider = |delta: T, x: NodeIdx, y: NodeIdx| {
+      if delta < best.map_or(T::zero(), |b| b.0) { best = Some((delta, x, y)); }
+    };
+
+    // swap a child with one of the children of its sibling, which
+    // replaces the sibling's volume with a volume containing the child
+    for (child, sibling) in [(b, c), (c, b)] {
+      if let NodeKind::Internal { child1: f, child2: g } = self.nodes[sibling].kind {
+        let sibling_measure = measure(self, sibling);
+        consider(join_measure(self, child, g) - sibling_measure, child, f);
+        consider(join_measure(self, child, f) - sibling_measure, child, g);
+      }
+    }
+
+    // swap a grandchild on the left with a grandchild on the right
+    if let (
+      NodeKind::Internal { child1: d, child2: e },
+      NodeKind::Internal { child1: f, child2: g }
+    ) = (&self.nodes[b].kind, &self.nodes[c].kind) {
+      let (d, e, f, g) = (*d, *e, *f, *g);
+      let old_measure = measure(self, b) + measure(self, c);
+      consider(join_measure(self, f, e) + join_measure(self, d, g) - old_measure, d, f);
+      consider(join_measure(self, g, e) + join_measure(self, f, d) - old_measure, d, g);
+    }
+
+    if let Some((_, x, y)) = best {
+      // both parents lie strictly below node_idx
+      let parent_x = self.nodes[x].parent.unwrap();
+      let parent_y = self.nodes[y].parent.unwrap();
+      self.swap_subtrees(x, y);
+      if parent_x != node_idx { self.refit_node(parent_x); }
+      if parent_y != node_idx { self.refit_node(parent_y); }
+    }
+  }
+
+  /// exchanges the positions of two nodes, neither of which is an ancestor
+  /// of the other, without refitting any volumes
+  fn swap_subtrees(&mut self, x: NodeIdx, y: NodeIdx) {
+    let parent_x = self.nodes[x].parent.unwrap();
+    let parent_y = self.nodes[y].parent.unwrap();
+
+    self.replace_child(parent_x, x, y);
+    self.replace_child(parent_y, y, x);
+    self.nodes[x].parent = Some(parent_y);
+    self.nodes[y].parent = Some(parent_x);
+  }
+
+  /// replaces `old_child` with `new_child` under an internal node
+  fn replace_child(&mut self, parent_idx: NodeIdx, old_child: NodeIdx, new_child: NodeIdx) {
+    match self.nodes[parent_idx].kind {
+      NodeKind::Internal { ref mut child1, ref mut child2 } => {
+        if *child1 == old_child { *child1 = new_child; }
+        else if *child2 == old_child { *child2 = new_child; }
+      }
+      _ => unreachable!("parent cannot be a leaf")
+    }
+  }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A structural invariant of a `Tree` which `Tree::validate` found violated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+  /// The root node has a parent.
+  RootHasParent { root: NodeIdx, parent: NodeIdx },
+  /// A node refers to a node which does not exist.
+  MissingNode { idx: NodeIdx },
+  /// A node's parent link disagrees with the node that has it as a child.
+  ParentMismatch { idx: NodeIdx, expected: NodeIdx, found: Option<NodeIdx> },
+  /// An internal node's volume does not contain one of its children.
+  VolumeNotContained { parent: NodeIdx, child: NodeIdx },
+  /// A node is reachable from the root along more than one path.
+  DuplicateNode { idx: NodeIdx },
+  /// A node is stored in the tree but is not reachable from the root.
+  Orphan { idx: NodeIdx },
+}
+
+impl std::fmt::Display for ValidationError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ValidationError::RootHasParent { root, parent } =>
+        write!(f, "root {root:?} has parent {parent:?}"),
+      ValidationError::MissingNode { idx } =>
+        write!(f, "node {idx:?} does not exist"),
+      ValidationError::ParentMismatch { idx, expected, found } =>
+        write!(f, "node {idx:?} has parent {found:?}, expected {expected:?}"),
+      ValidationError::VolumeNotContained { parent, child } =>
+        write!(f, "volume of node {parent:?} does not contain its child {child:?}"),
+      ValidationError::DuplicateNode { idx } =>
+        write!(f, "node {idx:?} is reachable along more than one path"),
+      ValidationError::Orphan { idx } =>
+        write!(f, "node {idx:?} is not reachable from the root"),
+    }
+  }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl<D, T: Real, const N: usize> TreeN<D, T, N> {
+  /// Checks the structural invariants of the tree: the root has no parent,
+  /// parent and child links agree, every internal volume contains its
+  /// children, and every stored node is reachable from the root exactly once.
+  pub fn validate(&self) -> Result<(), ValidationError> {
+    let mut visited = slotmap::SecondaryMap::<NodeIdx, ()>::new();
+
+    if let Some(root_idx) = self.root_idx {
+      let root = self.nodes.get(root_idx)
+        .ok_or(ValidationError::MissingNode { idx: root_idx })?;
+      if let Some(parent) = root.parent {
+        return Err(ValidationError::RootHasParent { root: root_idx, parent });
+      }
+
+      let mut stack = vec![root_idx];
+      while let Some(idx) = stack.pop() {
+        if visited.insert(idx, ()).is_some() {
+          return Err(ValidationError::DuplicateNode { idx });
+        }
+
+        let node = &self.nodes[idx];
+        if let NodeKind::Internal { child1, child2 } = node.kind {
+          for child_idx in [child1, child2] {
+            let child = self.nodes.get(child_idx)
+              .ok_or(ValidationError::MissingNode { idx: child_idx })?;
+            if child.parent != Some(idx) {
+              return Err(ValidationError::ParentMismatch {
+                idx: child_idx, expected: idx, found: child.parent
+              });
+            }
+            if !node.volume.contains(&child.volume) {
+              return Err(ValidationError::VolumeNotContained { parent: idx, child: child_idx });
+            }
+            stack.push(child_idx);
+          }
+        }
+      }
+    }
+
+    match self.nodes.keys().find(|idx| !visited.contains_key(*idx)) {
+      Some(idx) => Err(ValidationError::Orphan { idx }),
+      None      => Ok(())
+    }
+  }
+
+  /// with the `validate-bvh` feature, panics if the tree is invalid
+  fn debug_validate(&self) {
+    #[cfg(feature = "validate-bvh")]
+    if let Err(err) = self.validate() {
+      panic!("invalid bvh: {err}");
+    }
+  }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Number of bins used to approximate the SAH cost of each candidate split.
+const SAH_BINS: usize = 16;
+
+impl<D, T: Real, const N: usize> TreeN<D, T, N> {
+  /// Builds a tree containing every `(volume, data)` pair using top-down
+  /// binned SAH splits, which usually produces a much better tree than
+  /// inserting the leaves one by one.  Returns the tree along with the
+  /// handle of each leaf, in the order the leaves were given.
+  pub fn build_from<I>(leaves: I) -> (Self, Vec<NodeIdx>)
+  where I: IntoIterator<Item = (AABBN<T, N>, D)> {
+    return TreeN::build_from_with_margin(
+      leaves,
+      scalar(DEFAULT_MARGIN.into()),
+      scalar(DEFAULT_DISPLACEMENT_MULTIPLIER.into())
+    );
+  }
+
+  /// Like `build_from`, but leaf volumes are fattened as for `with_margin`.
+  pub fn build_from_with_margin<I>(
+    leaves: I,
+    margin: T,
+    displacement_multiplier: T
+  ) -> (Self, Vec<NodeIdx>)
+  where I: IntoIterator<Item = (AABBN<T, N>, D)> {
+    let mut tree = TreeN::with_margin(margin, displacement_multiplier);
+
+    let handles: Vec<NodeIdx> = leaves.into_iter().map(|(volume, data)| {
+      tree.nodes.insert(Node {
+        volume : tree.fatten(&volume, SVector::zeros()),
+        parent : None,
+        kind   : NodeKind::Leaf { data }
+      })
+    }).collect();
+
+    let mut leaf_idxs = handles.clone();
+    tree.build_top_down(&mut leaf_idxs);
+    tree.debug_validate();
+    (tree, handles)
+  }
+
+  /// Discards every internal node and rebuilds the tree top-down with binned
+  /// SAH splits.  Leaf handles remain valid.
+  pub fn rebuild(&mut self) {
+    // gather the leaves in tree order rather than arena order, so that trees
+    // with the same structure rebuild identically whatever their handles
+    let mut leaf_idxs = vec![];
+    self.for_each_node_with_depth(|idx, node, _| {
+      if let NodeKind::Leaf { .. } = node.kind { leaf_idxs.push(idx); }
+    });
+
+    self.nodes.retain(|_, node| matches!(node.kind, NodeKind::Leaf { .. }));
+    for &leaf_idx in &leaf_idxs {
+      self.nodes[leaf_idx].parent = None;
+    }
+
+    self.build_top_down(&mut leaf_idxs);
+    self.debug_validate();
+  }
+
+  /// builds a tree over detached leaves and makes it the root
+  fn build_top_down(&mut self, leaf_idxs: &mut [NodeIdx]) {
+    self.root_idx = if leaf_idxs.is_empty() {
+      None
+    } else {
+      Some(self.build_subtree(leaf_idxs))
+    };
+  }
+
+  /// builds a subtree over detached leaves, returning its root, using an
+  /// explicit stack so that degenerate splits cannot overflow the call stack
+  fn build_subtree(&mut self, leaf_idxs: &mut [NodeIdx]) -> NodeIdx {
+    // ranges of leaves are pushed once to be split, and again once both
+    // halves have been built and their roots left on top of `roots`
+    let mut stack = vec![(0, leaf_idxs.len(), false)];
+    let mut roots = Vec::new();
+
+    while let Some((start, end, expanded)) = stack.pop() {
+      if end - start == 1 {
+        roots.push(leaf_idxs[start]);
+      } else if expanded {
+        let child2 = roots.pop().unwrap();
+        let child1 = roots.pop().unwrap();
+        let node_idx = self.nodes.insert(Node {
+          parent : None,
+          volume : AABBN::join(&self.nodes[child1].volume, &self.nodes[child2].volume),
+          kind   : NodeKind::Internal { child1, child2 }
+        });
+
+        self.nodes[child1].parent = Some(node_idx);
+        self.nodes[child2].parent = Some(node_idx);
+        roots.push(node_idx);
+      } else {
+        let split = start + self.partition_sah(&mut leaf_idxs[start..end]);
+        stack.push((start, end, true));
+        stack.push((split, end, false));
+        stack.push((start, split, false));
+      }
+    }
+
+    roots.pop().unwrap()
+  }
+
+  /// Reorders at least two leaves so that the best split under the binned
+  /// surface area heuristic places `leaf_idxs[..split]` on the left, and
+  /// returns `split`.  Leaves are binned by their centers along the axis in
+  /// which the centers are most spread out.
+  fn partition_sah(&self, leaf_idxs: &mut [NodeIdx]) -> usize {
+    let center = |idx: NodeIdx| self.nodes[idx].volume.center();
+    let centers = AABBN::from_points(leaf_idxs.iter().map(|&idx| center(idx))).unwrap();
+    let spread = centers.upper_bound - centers.lower_bound;
+    let axis = spread.imax();
+
+    // all centers coincide, so split in half to keep the tree balanced
+    if spread[axis] <= T::default_epsilon() { return leaf_idxs.len() / 2; }
+
+    let bin_of = |idx: NodeIdx| {
+      let t = (center(idx)[axis] - centers.lower_bound[axis]) / spread[axis];
+      ((to_f64(t) * SAH_BINS as f64) as usize).min(SAH_BINS - 1)
+    };
+
+    // accumulate the volume and number of leaves in each bin
+    let mut bins: [(Option<AABBN<T, N>>, usize); SAH_BINS] = [(None, 0); SAH_BINS];
+    for &idx in leaf_idxs.iter() {
+      let volume = &self.nodes[idx].volume;
+      let bin = &mut bins[bin_of(idx)];
+      bin.0 = Some(bin.0.map_or(*volume, |v| AABBN::join(&v, volume)));
+      bin.1 += 1;
+    }
+
+    // SAH cost of splitting between bins i-1 and i, i.e. the surface
+    // area of each side weighted by the number of leaves on that side
+    let side_cost = |bins: &[(Option<AABBN<T, N>>, usize)]| {
+      let volume = bins.iter().filter_map(|b| b.0).reduce(|a, b| AABBN::join(&a, &b));
+      let count: usize = bins.iter().map(|b| b.1).sum();
+      volume.map_or(T::zero(), |v| v.sah_measure() * scalar(count as f64))
+    };
+
+    let mut best_bin = 1;
+    let mut best_cost = scalar(f64::INFINITY);
+    for i in 1..SAH_BINS {
+      let cost = side_cost(&bins[..i]) + side_cost(&bins[i..]);
+      if cost < best_cost {
+        best_bin = i;
+        best_cost = cost;
+      }
+    }
+
+    // move leaves in bins below best_bin to the front
+    let mut split = 0;
+    for i in 0..leaf_idxs.len() {
+      if bin_of(leaf_idxs[i]) < best_bin {
+        leaf_idxs.swap(i, split);
+        split += 1;
+      }
+    }
+
+    // the extreme centers lie in the first and last bins, so both sides
+    // are non-empty, but guard against floating point surprises anyway
+    if split == 0 || split == leaf_idxs.len() { leaf_idxs.len() / 2 } else { split }
+  }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
-fn tree_cost<D>(tree: &Tree<D>) -> f32 {
-  let mut cost = 0.0;
-  // we only compare trees with the same leaf nodes,
-  // so they are excluded from the cost computation
-  for (_, node) in &tree.nodes {
-    if let NodeKind::Internal { .. } = node.kind {
-      cost += node.volume.surface_area();
+impl<D, T: Real, const N: usize> TreeN<D, T, N> {
+  /// Returns the total cost of the tree under the surface area heuristic.
+  pub fn tree_cost(&self) -> T {
+    let mut cost = T::zero();
+    // in 2D, the perimeter plays the role of the surface area;
+    // we only compare trees with the same leaf nodes,
+    // so they are excluded from the cost computation.
+    // summing in tree order keeps the result independent of handles
+    self.for_each_node_with_depth(|_, node, _| {
+      if let NodeKind::Internal { .. } = node.kind {
+        cost += node.volume.sah_measure();
+      }
+    });
+    cost
+  }
+
+  /// Returns the number of edges on the longest path from the root to a leaf,
+  /// or zero for an empty tree.
+  pub fn height(&self) -> usize {
+    self.root_idx.map_or(0, |root_idx| self.subtree_balance(root_idx).0)
+  }
+
+  /// Returns the largest difference in height between
+  /// the two children of any internal node.
+  pub fn max_balance(&self) -> usize {
+    self.root_idx.map_or(0, |root_idx| self.subtree_balance(root_idx).1)
+  }
+
+  /// computes the height and maximum balance of a subtree in a
+  /// single post-order traversal, without recursion
+  fn subtree_balance(&self, root_idx: NodeIdx) -> (usize, usize) {
+    let mut heights = slotmap::SecondaryMap::<NodeIdx, usize>::new();
+    let mut max_balance = 0;
+
+    // nodes are pushed once on the way down, and again
+    // once their children have been assigned heights
+    let mut stack = vec![(root_idx, false)];
+
+    while let Some((idx, expanded)) = stack.pop() {
+      match self.nodes[idx].kind {
+        NodeKind::Leaf { .. } => { heights.insert(idx, 0); }
+        NodeKind::Internal { child1, child2 } if expanded => {
+          let (h1, h2) = (heights[child1], heights[child2]);
+          max_balance = max_balance.max(h1.abs_diff(h2));
+          heights.insert(idx, 1 + h1.max(h2));
+        }
+        NodeKind::Internal { child1, child2 } => {
+          stack.push((idx, true));
+          stack.push((child1, false));
+          stack.push((child2, false));
+        }
+      }
     }
+
+    (heights[root_idx], max_balance)
   }
-  cost
 }
 
+////////////////////////////////////////////////////////////////////////////////
+
+use std::fmt::Write;
+
+impl<D, T: Real, const N: usize> TreeN<D, T, N> {
+  /// Visits every node in depth-first pre-order along with its depth, where
+  /// the root has depth zero.
+  fn for_each_node_with_depth<F>(&self, mut callback: F)
+  where F: FnMut(NodeIdx, &Node<D, T, N>, usize) {
+    let Some(root_idx) = self.root_idx else { return; };
+    let mut stack = vec![(root_idx, 0)];
+
+    while let Some((idx, depth)) = stack.pop() {
+      let node = &self.nodes[idx];
+      callback(idx, node, depth);
+
+      if let NodeKind::Internal { child1, child2 } = node.kind {
+        stack.push((child2, depth + 1));
+        stack.push((child1, depth + 1));
+      }
+    }
+  }
+
+  /// Invokes the callback with the volume and depth of every internal node in
+  /// depth-first pre-order, where the root has depth zero.
+  pub fn for_each_internal_node<F>(&self, mut callback: F)
+  where F: FnMut(NodeIdx, &AABBN<T, N>, usize) {
+    self.for_each_node_with_depth(|idx, node, depth| {
+      if let NodeKind::Internal { .. } = node.kind {
+        callback(idx, &node.volume, depth);
+      }
+    });
+  }
+
+  /// Renders the tree as a Graphviz graph, e.g. for `dot -Tsvg`.  Each node is
+  /// labelled with its volume, and each internal node also with its
+  /// contribution to the SAH cost, i.e. its perimeter in 2D.  The graph is
+  /// labelled with the total `tree_cost`, which sums those contributions.
+  pub fn to_dot(&self) -> String {
+    let mut ids = slotmap::SecondaryMap::<NodeIdx, usize>::new();
+    let mut dot = String::new();
+
+    writeln!(dot, "digraph bvh {{").unwrap();
+    writeln!(dot, "  label=\"cost {:.4}, height {}\";", self.tree_cost(), self.height()).unwrap();
+    writeln!(dot, "  node [shape=box, fontname=\"monospace\"];").unwrap();
+
+    self.for_each_node_with_depth(|idx, node, _| {
+      let id = ids.len();
+      ids.insert(idx, id);
+
+      // leaves are excluded from `tree_cost`, so only internal nodes show a cost
+      let (kind, cost, style) = match node.kind {
+        NodeKind::Leaf { .. } => ("leaf", String::new(), ", style=filled, fillcolor=\"#dddddd\""),
+        NodeKind::Internal { .. } => {
+          ("internal", format!("\\nsah cost {:.4}", node.volume.sah_measure()), "")
+        }
+      };
+
+      writeln!(
+        dot,
+        "  n{id} [label=\"{kind}\\n{}\\n{}{cost}\"{style}];",
+        format_point(&node.volume.lower_bound),
+        format_point(&node.volume.upper_bound)
+      ).unwrap();
+
+      // parents are always visited before their children
+      if let Some(parent) = node.parent {
+        writeln!(dot, "  n{} -> n{id};", ids[parent]).unwrap();
+      }
+    });
+
+    writeln!(dot, "}}").unwrap();
+    dot
+  }
+}
+
+/// formats a point as a tuple with a few decimal places
+fn format_point<T: Real, const N: usize>(point: &SVector<T, N>) -> String {
+  let coords: Vec<String> = point.iter().map(|x| format!("{x:.3}")).collect();
+  format!("({})", coords.join(", "))
+}
+
+impl<D, T: Real> TreeN<D, T, 2> {
+  /// Draws the volume of every node as an SVG image, with the outline colour
+  /// cycling through hues by depth and leaves lightly filled.  The y-axis
+  /// points up, to match world coordinates.
+  pub fn to_svg(&self) -> String {
+    let mut svg = String::new();
+
+    let bounds = match self.root_idx {
+      Some(root_idx) => self.nodes[root_idx].volume,
+      None => AABBN::new(SVector::zeros(), SVector::zeros())
+    };
+
+    // leave some space around the root volume
+    let size = bounds.upper_bound - bounds.lower_bound;
+    let pad = size.x.max(size.y).max(T::one()) * scalar(0.05);
+    let view = bounds.expand_by_margin(pad);
+    let (width, height) = (view.upper_bound.x - view.lower_bound.x, view.upper_bound.y - view.lower_bound.y);
+
+    writeln!(
+      svg,
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+      view.lower_bound.x, -view.upper_bound.y, width, height
+    ).unwrap();
+    writeln!(svg, "<g transform=\"scale(1,-1)\" stroke-width=\"1\">").unwrap();
+
+    let num_levels = self.height() + 1;
+    self.for_each_node_with_depth(|_, node, depth| {
+      let hue = 360 * depth / num_levels;
+      let fill = match node.kind {
+        NodeKind::Leaf { .. } => format!("hsla({hue}, 80%, 50%, 0.15)"),
+        NodeKind::Internal { .. } => "none".to_string(),
+      };
+
+      let (lower, upper) = (node.volume.lower_bound, node.volume.upper_bound);
+      writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" stroke=\"hsl({hue}, 80%, 40%)\" fill=\"{fill}\" vector-effect=\"non-scaling-stroke\"/>",
+        lower.x, lower.y, upper.x - lower.x, upper.y - lower.y
+      ).unwrap();
+    });
+
+    writeln!(svg, "</g>").unwrap();
+    writeln!(svg, "</svg>").unwrap();
+    svg
+  }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 use std::cmp::Reverse;
 use ordered_float::OrderedFloat;
 
+// priority queue keys are widened to f64, which is
+// exact for both f32 and f64, so that they are `Ord`
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 struct Candidate {
   /// estimated cost of replacing self with a new
   /// parent node whose children are this and V
-  cost_estimate: Reverse<OrderedFloat<f32>>,
+  cost_estimate: Reverse<OrderedFloat<f64>>,
   /// increased surface area caused by refitting
   /// all ancestors of this node to include V
-  cost_inherit: Reverse<OrderedFloat<f32>>,
+  cost_inherit: Reverse<OrderedFloat<f64>>,
+  /// position in the order candidates were queued, which breaks ties so that
+  /// the search does not depend on handle values, e.g. after deserializing
+  order: Reverse<usize>,
   /// index of this candidate
   idx: NodeIdx
 }
 
 impl Candidate {
-  fn new(cost_estimate: f32, cost_inherit: f32, idx: NodeIdx) -> Self {
+  fn new<T: Real>(cost_estimate: T, cost_inherit: T, order: usize, idx: NodeIdx) -> Self {
     Candidate {
-      cost_estimate : Reverse(OrderedFloat(cost_estimate)),
-      cost_inherit  : Reverse(OrderedFloat(cost_inherit)),
+      cost_estimate : Reverse(OrderedFloat(to_f64(cost_estimate))),
+      cost_inherit  : Reverse(OrderedFloat(to_f64(cost_inherit))),
+      order         : Reverse(order),
       idx
     }
   }
 }
 
-// increased surface area due to refitting nodes[idx] to include volume
-fn delta_cost<D>(tree: &Tree<D>, volume: &AABB, idx: NodeIdx) -> f32 {
-  let node = &tree.nodes[idx];
-  let old_cost = node.volume.surface_area();
-  let new_cost = AABB::join(&node.volume, volume).surface_area();
-  return new_cost - old_cost;
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+struct Neighbour {
+  /// lower bound on the distance from the query point,
+  /// or the exact distance once a leaf has been refined
+  dist: Reverse<OrderedFloat<f64>>,
+  /// whether `dist` has been refined by the distance callback
+  refined: bool,
+  /// position in the order nodes were queued, which breaks ties so that
+  /// results do not depend on handle values, e.g. after deserializing
+  order: Reverse<usize>,
+  /// index of this node
+  idx: NodeIdx
+}
+
+impl Neighbour {
+  fn new<T: Real>(dist: T, refined: bool, order: usize, idx: NodeIdx) -> Self {
+    Neighbour { dist: Reverse(OrderedFloat(to_f64(dist))), refined, order: Reverse(order), idx }
+  }
 }
 
-fn find_best_sibling<D>(tree: &Tree<D>, root_idx: NodeIdx, volume: &AABB) -> NodeIdx {
+fn find_best_sibling<D, T: Real, const N: usize>(tree: &TreeN<D, T, N>, root_idx: NodeIdx, volume: &AABBN<T, N>) -> NodeIdx {
   // priority queue of candidate nodes
   let mut priority_queue = BinaryHeap::new();
   
   // Cost(Root) = Area(V ∪ Root)
-  let root_cost = delta_cost(tree, volume, root_idx);
-  let root_candidate = Candidate::new(root_cost, 0.0, root_idx); 
+  let root_cost = AABBN::join(volume, &tree.nodes[root_idx].volume).sah_measure();
+  let root_candidate = Candidate::new(root_cost, T::zero(), 0, root_idx); 
   priority_queue.push(root_candidate);
+  let mut num_queued = 1;
   
   // branch and bound
   let mut best_idx = root_idx;
@@ -261,15 +1340,15 @@ fn find_best_sibling<D>(tree: &Tree<D>, root_idx: NodeIdx, volume: &AABB) -> Nod
     // direct cost is the surface area of the new internal
     // node that will be created to hold new leaf and sibling
     //   DirectCost(C) = Area(C ∪ V)
-    let direct_cost = AABB::join(volume, &current_node.volume).surface_area();
+    let direct_cost = AABBN::join(volume, &current_node.volume).sah_measure();
 
     // increased surface area caused by refitting C to include V
     //   DeltaCost(C) = Area(C ∪ V) - Area(C)
-    let delta_cost = direct_cost - current_node.volume.surface_area();
+    let delta_cost = direct_cost - current_node.volume.sah_measure();
 
     // inherited cost is the increased surface area
     // caused by refitting volumes of all ancestors
-    let inherited_cost = current.cost_inherit.0.0;
+    let inherited_cost = scalar::<T>(current.cost_inherit.0.0);
 
     // TotalCost(C) = DirectCost(C) + InheritedCost(C)
     let total_cost = direct_cost + inherited_cost;
@@ -288,15 +1367,17 @@ fn find_best_sibling<D>(tree: &Tree<D>, root_idx: NodeIdx, volume: &AABB) -> Nod
       //    >= Area(D ∪ V)   + InheritedCost(C) + DeltaCost(C)    (D descendent of C)
       //    >= Area(V)       + InheritedCost(C) + DeltaCost(C)    (monotonicity of area)
       let cost_passed_to_children = inherited_cost + delta_cost;
-      let child_lower_bound = volume.surface_area() + cost_passed_to_children;
+      let child_lower_bound = volume.sah_measure() + cost_passed_to_children;
 
       if child_lower_bound < best_cost {
         for child in [child1, child2] {
           priority_queue.push(Candidate::new(
             child_lower_bound,
             cost_passed_to_children,
+            num_queued,
             child
           ));
+          num_queued += 1;
         }
       }
     }
@@ -1,45 +1,235 @@
 // https://dev.to/deciduously/no-more-tears-no-more-knots-arena-allocated-trees-in-rust-44k6
 
-#[derive(Debug, Default)]
-struct ArenaTree<T: PartialEq>
+use std::collections::VecDeque;
+use slotmap::{new_key_type, SlotMap};
+
+////////////////////////////////////////////////////////////
+
+// slotmap provides a macro for defining the index type
+new_key_type! {
+  /// Stable handle to a node of an `ArenaTree`, which remains
+  /// valid until the subtree containing the node is removed.
+  pub struct NodeId;
+}
+
+/// An n-ary forest whose nodes are stored in a single arena.  Nodes refer to
+/// their parent and children by `NodeId`, so the structure can be freely
+/// edited without fighting the borrow checker.
+#[derive(Debug)]
+pub struct ArenaTree<T>
 {
-  arena: Vec<Node<T>>
+  arena: SlotMap<NodeId, Node<T>>
 }
 
-impl<T: PartialEq> ArenaTree<T> {
-  pub fn size(&self, val: T) -> usize {
+impl<T> Default for ArenaTree<T> {
+  fn default() -> Self {
+    ArenaTree { arena: SlotMap::default() }
+  }
+}
+
+impl<T> ArenaTree<T> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// number of nodes in the forest
+  pub fn size(&self) -> usize {
     self.arena.len()
   }
 
+  pub fn is_empty(&self) -> bool {
+    self.arena.is_empty()
+  }
+
   pub fn edges(&self) -> usize {
-    self.arena.iter().fold(0, |acc,node| acc + node.children.len())
+    self.arena.values().fold(0, |acc,node| acc + node.children.len())
+  }
+
+  /// number of edges between a node and the root of its tree
+  pub fn depth(&self, id: NodeId) -> usize {
+    self.ancestors(id).count().saturating_sub(1)
+  }
+
+  pub fn contains(&self, id: NodeId) -> bool {
+    self.arena.contains_key(id)
+  }
+
+  pub fn get(&self, id: NodeId) -> Option<&T> {
+    self.arena.get(id).map(|node| &node.val)
+  }
+
+  pub fn get_mut(&mut self, id: NodeId) -> Option<&mut T> {
+    self.arena.get_mut(id).map(|node| &mut node.val)
+  }
+
+  pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+    self.arena.get(id).and_then(|node| node.parent)
+  }
+
+  /// children of a node in insertion order, or empty if the node is missing
+  pub fn children(&self, id: NodeId) -> &[NodeId] {
+    self.arena.get(id).map_or(&[], |node| &node.children)
+  }
+
+  /// iterates over the nodes which have no parent
+  pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+    self.arena.iter()
+      .filter(|(_, node)| node.parent.is_none())
+      .map(|(id, _)| id)
+  }
+
+  /// adds a new node without a parent, i.e. the root of a new tree
+  pub fn add_node(&mut self, val: T) -> NodeId {
+    self.arena.insert(Node::new(val))
+  }
+
+  /// adds a new node as the last child of `parent`,
+  /// or returns `None` if `parent` does not exist
+  pub fn add_child(&mut self, parent: NodeId, val: T) -> Option<NodeId> {
+    if !self.arena.contains_key(parent) { return None; }
+
+    let mut node = Node::new(val);
+    node.parent = Some(parent);
+
+    let id = self.arena.insert(node);
+    self.arena[parent].children.push(id);
+    Some(id)
+  }
+
+  /// Removes a node along with all of its descendants, returning their values
+  /// in depth-first pre-order, or `None` if the node does not exist.
+  pub fn remove_subtree(&mut self, id: NodeId) -> Option<Vec<T>> {
+    if !self.arena.contains_key(id) { return None; }
+
+    // detach from parent
+    if let Some(parent) = self.arena[id].parent {
+      self.arena[parent].children.retain(|&child| child != id);
+    }
+
+    let ids: Vec<NodeId> = self.dfs(id).collect();
+    Some(ids.into_iter().map(|id| self.arena.remove(id).unwrap().val).collect())
+  }
+
+  /// iterates over a node and its ancestors, ending at the root
+  pub fn ancestors(&self, id: NodeId) -> Ancestors<'_, T> {
+    Ancestors { tree: self, next: self.arena.get(id).map(|_| id) }
+  }
+
+  /// iterates over a subtree in depth-first pre-order
+  pub fn dfs(&self, root: NodeId) -> Dfs<'_, T> {
+    let stack = if self.contains(root) { vec![root] } else { vec![] };
+    Dfs { tree: self, stack }
   }
 
-  pub fn depth(&self, idx: usize) -> usize {
-    match self.arena[idx].parent {
-      Some(id) => 1 + self.depth(id),
-      None     => 0
+  /// iterates over a subtree in breadth-first order
+  pub fn bfs(&self, root: NodeId) -> Bfs<'_, T> {
+    let queue = if self.contains(root) { VecDeque::from([root]) } else { VecDeque::new() };
+    Bfs { tree: self, queue }
+  }
+
+  /// Returns the deepest node which is an ancestor of both `a` and `b`, where
+  /// each node counts as its own ancestor, or `None` if they are in different
+  /// trees of the forest.
+  pub fn lowest_common_ancestor(&self, a: NodeId, b: NodeId) -> Option<NodeId> {
+    if !self.contains(a) || !self.contains(b) { return None; }
+
+    let (mut a, mut b) = (a, b);
+    let (mut depth_a, mut depth_b) = (self.depth(a), self.depth(b));
+
+    // walk the deeper node up until both are at the same depth
+    while depth_a > depth_b { a = self.arena[a].parent?; depth_a -= 1; }
+    while depth_b > depth_a { b = self.arena[b].parent?; depth_b -= 1; }
+
+    // then walk both up in lockstep until they meet
+    while a != b {
+      a = self.arena[a].parent?;
+      b = self.arena[b].parent?;
     }
+
+    Some(a)
+  }
+
+  /// Returns the nodes along the path from `a` to `b`, inclusive, passing
+  /// through their lowest common ancestor, or `None` if there is no path.
+  pub fn path(&self, a: NodeId, b: NodeId) -> Option<Vec<NodeId>> {
+    let lca = self.lowest_common_ancestor(a, b)?;
+
+    // a up to, and including, the common ancestor
+    let mut path: Vec<NodeId> = self.ancestors(a).take_while(|&id| id != lca).collect();
+    path.push(lca);
+
+    // then down to b
+    let mut down: Vec<NodeId> = self.ancestors(b).take_while(|&id| id != lca).collect();
+    down.reverse();
+    path.extend(down);
+
+    Some(path)
+  }
+}
+
+////////////////////////////////////////////////////////////
+
+pub struct Ancestors<'a, T> {
+  tree: &'a ArenaTree<T>,
+  next: Option<NodeId>
+}
+
+impl<T> Iterator for Ancestors<'_, T> {
+  type Item = NodeId;
+
+  fn next(&mut self) -> Option<NodeId> {
+    let id = self.next?;
+    self.next = self.tree.arena[id].parent;
+    Some(id)
+  }
+}
+
+pub struct Dfs<'a, T> {
+  tree: &'a ArenaTree<T>,
+  stack: Vec<NodeId>
+}
+
+impl<T> Iterator for Dfs<'_, T> {
+  type Item = NodeId;
+
+  fn next(&mut self) -> Option<NodeId> {
+    let id = self.stack.pop()?;
+    // push in reverse so the first child is visited first
+    self.stack.extend(self.tree.arena[id].children.iter().rev());
+    Some(id)
+  }
+}
+
+pub struct Bfs<'a, T> {
+  tree: &'a ArenaTree<T>,
+  queue: VecDeque<NodeId>
+}
+
+impl<T> Iterator for Bfs<'_, T> {
+  type Item = NodeId;
+
+  fn next(&mut self) -> Option<NodeId> {
+    let id = self.queue.pop_front()?;
+    self.queue.extend(self.tree.arena[id].children.iter());
+    Some(id)
   }
 }
 
 ////////////////////////////////////////////////////////////
 
 #[derive(Debug)]
-struct Node<T: PartialEq> {
-  idx: usize,
+struct Node<T> {
   val: T,
-  parent: Option<usize>,
-  children: Vec<usize>
+  parent: Option<NodeId>,
+  children: Vec<NodeId>
 }
 
-impl<T: PartialEq> Node<T> {
-  pub fn new(idx: usize, val: T) -> Self {
+impl<T> Node<T> {
+  pub fn new(val: T) -> Self {
     Self {
-      idx,
       val,
       parent: None,
       children: vec![]
     }
   }
-}
\ No newline at end of file
+}
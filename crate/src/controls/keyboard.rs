@@ -4,7 +4,9 @@ pub enum Key {
   ArrowRight,
   ArrowUp,
   ArrowDown,
-  Space
+  Space,
+  KeyB,
+  KeyH
 }
 
 pub fn convert_key(key: String) -> Option<Key> {
@@ -14,6 +16,8 @@ pub fn convert_key(key: String) -> Option<Key> {
     "ArrowUp"    => { Some(Key::ArrowUp)    }
     "ArrowDown"  => { Some(Key::ArrowDown)  }
     " "          => { Some(Key::Space)      }
+    "b" | "B"    => { Some(Key::KeyB)       }
+    "h" | "H"    => { Some(Key::KeyH)       }
     _ => None
   }
 }
\ No newline at end of file
@@ -16,7 +16,7 @@ pub struct Collider {
 
 #[derive(Component)]
 pub struct Geom2d {
-  pub shape: geom::ConvexPoly
+  pub shape: geom::Shape
 }
 
 #[derive(Component)]
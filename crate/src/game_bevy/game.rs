@@ -3,7 +3,7 @@ use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 use bevy_ecs::{event::EventRegistry, prelude::*, schedule::ScheduleLabel};
 use glow::{Context, HasContext};
 
-use crate::game_bevy::{events::InputEvent, resources::game_state::{game_state_event_listener, GameState}, systems::{event_system::{event_writer_system, EventQueue, EventQueueResource}, physics_system, player_control_system::player_control_system, render_system::{render_system, RenderResource}}};
+use crate::game_bevy::{events::InputEvent, resources::game_state::{game_state_event_listener, GameState}, systems::{broadphase_system::{broadphase_system, broadphase_toggle_system, BroadphasePairs, BroadphaseResource}, event_system::{event_writer_system, EventQueue, EventQueueResource}, physics_system, player_control_system::player_control_system, render_system::{render_system, RenderResource}}};
 
 /* -------------------------------------------- */
 
@@ -65,9 +65,14 @@ impl Game {
       key_down: false,
       key_left: false,
       key_right: false,
-      key_up: false
+      key_up: false,
+      show_broadphase: false,
+      use_spatial_hash: false
     });
 
+    world.insert_resource(BroadphaseResource::new());
+    world.insert_resource(BroadphasePairs::default());
+
     /* ---- update schedule ---- */
     let mut update_schedule = Schedule::new(Update);
     // update_schedule.add_systems(
@@ -80,7 +85,9 @@ impl Game {
       (event_writer_system,
         game_state_event_listener,
       player_control_system,
-      physics_system)
+      physics_system,
+      broadphase_toggle_system.before(broadphase_system),
+      broadphase_system.after(physics_system))
     );
 
     /* ---- render schedule ---- */
@@ -7,7 +7,11 @@ pub struct GameState {
   pub key_left: bool,
   pub key_right: bool,
   pub key_up: bool,
-  pub key_down: bool
+  pub key_down: bool,
+  /// whether to draw the broadphase structure over the scene
+  pub show_broadphase: bool,
+  /// whether to use the toroidal spatial hash instead of the default tree
+  pub use_spatial_hash: bool
 }
 
 pub fn game_state_event_listener(
@@ -26,6 +30,8 @@ pub fn game_state_event_listener(
           Key::ArrowUp    => { game_state.key_up    = true; }
           Key::ArrowLeft  => { game_state.key_left  = true; }
           Key::ArrowRight => { game_state.key_right = true; }
+          Key::KeyB       => { game_state.show_broadphase = !game_state.show_broadphase; }
+          Key::KeyH       => { game_state.use_spatial_hash = !game_state.use_spatial_hash; }
           _ => { }
         };
       }
@@ -1,7 +1,6 @@
 use rand::prelude::*;
 
 use crate::geom;
-use crate::bvh::aabb::AABB;
 use crate::game_bevy::game;
 use crate::game_bevy::components;
 
@@ -9,13 +8,12 @@ pub fn create_scene1(game: &game::Game) {
   let mut state = game.state.borrow_mut();
 
   // spawn player
+  let shape = geom::Shape::Polygon(geom::ConvexPoly::regular(3, 0.04));
+  let volume = shape.aabb(&nalgebra::Isometry2::identity());
   state.world.spawn((
     components::Player,
-    components::Geom2d { shape : geom::ConvexPoly::regular(3, 0.04) },
-    components::Collider { volume : AABB {
-      lower_bound : nalgebra::Vector2::new(-0.05, 0.05),
-      upper_bound : nalgebra::Vector2::new(-0.05, 0.05)
-    } },
+    components::Geom2d { shape },
+    components::Collider { volume },
     components::Position { pos : (0.0, 0.0) },
     components::Velocity { x : 0.0, y : 0.0 }
   ));
@@ -28,17 +26,17 @@ pub fn create_scene1(game: &game::Game) {
     let vx = 0.001 * (rand::random::<f32>() * 2.0 - 1.0);
     let vy = 0.001 * (rand::random::<f32>() * 2.0 - 1.0);
 
-    let shape = geom::ConvexPoly::regular(n, 0.08);
-    
-    let min_x = shape.points.row(0).min();
-    let max_x = shape.points.row(0).max();
-    let min_y = shape.points.row(1).min();
-    let max_y = shape.points.row(1).max();
-
-    let aabb  = AABB {
-      lower_bound : nalgebra::Vector2::new(min_x, min_y),
-      upper_bound : nalgebra::Vector2::new(max_x, max_y)
+    // a mix of round, elongated and angular asteroids
+    let shape = match rand::thread_rng().gen_range(0, 3) {
+      0 => geom::Shape::Circle { radius : 0.06 },
+      1 => geom::Shape::Capsule {
+        a      : nalgebra::Vector2::new(-0.04, 0.0),
+        b      : nalgebra::Vector2::new( 0.04, 0.0),
+        radius : 0.04
+      },
+      _ => geom::Shape::Polygon(geom::ConvexPoly::regular(n, 0.08))
     };
+    let aabb = shape.aabb(&nalgebra::Isometry2::identity());
 
     state.world.spawn((
       components::Geom2d { shape },
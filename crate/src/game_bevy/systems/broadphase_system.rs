@@ -0,0 +1,121 @@
+use bevy_ecs::prelude::*;
+use nalgebra::Vector2;
+
+use crate::broadphase::{Broadphase, BvhBroadphase, SpatialHash};
+use crate::bvh::aabb::AABB;
+use crate::game_bevy::components;
+use crate::game_bevy::resources::game_state::GameState;
+use crate::game_bevy::systems::world_bounds;
+
+/* ---------------------------------------- */
+
+/// Broadphase holding the world-space volume of every `Collider`.  Defaults
+/// to a dynamic AABB tree, but scenes may replace it with any `Broadphase`.
+#[derive(Resource)]
+pub struct BroadphaseResource {
+  pub broadphase: Box<dyn Broadphase<Entity> + Send + Sync>
+}
+
+impl BroadphaseResource {
+  pub fn new() -> BroadphaseResource {
+    return BroadphaseResource::with(BvhBroadphase::new());
+  }
+
+  pub fn with<B>(broadphase: B) -> BroadphaseResource
+  where B: Broadphase<Entity> + Send + Sync + 'static {
+    return BroadphaseResource {
+      broadphase: Box::new(broadphase)
+    }
+  }
+}
+
+/// Potentially-overlapping pairs of entities found by the broadphase during
+/// the most recent tick.  Each pair is ordered `(a, b)` with `a < b`, and the
+/// list is sorted and contains no duplicates.
+#[derive(Resource, Default)]
+pub struct BroadphasePairs {
+  pub pairs: Vec<(Entity, Entity)>
+}
+
+/* ---------------------------------------- */
+
+type BroadphaseData<'a> = (
+  Entity,
+  &'a     components::Collider,
+  &'a     components::Position,
+  Option<&'a components::Velocity>
+);
+
+type BroadphaseFilter = Or<(
+  Changed<components::Collider>,
+  Changed<components::Position>
+)>;
+
+/// collider volumes are relative to the entity position
+fn world_volume(collider: &components::Collider, pos: &components::Position) -> AABB {
+  return collider.volume.translate(Vector2::new(pos.pos.0, pos.pos.1));
+}
+
+pub fn broadphase_system(
+  mut broadphase: ResMut<BroadphaseResource>,
+  mut pairs: ResMut<BroadphasePairs>,
+  mut removed: RemovedComponents<components::Collider>,
+  data: Query<BroadphaseData, BroadphaseFilter>
+) {
+  let broadphase = &mut broadphase.broadphase;
+
+  // remove colliders which were despawned or removed
+  for entity in removed.read() {
+    broadphase.remove(entity);
+  }
+
+  // insert untracked colliders and move tracked ones; a collider is untracked
+  // until its entity also has a position, or if it existed before this system
+  // first ran, so checking the broadphase is more robust than `is_added`
+  for (entity, collider, pos, vel) in data {
+    let volume = world_volume(collider, pos);
+
+    if !broadphase.contains(&entity) {
+      broadphase.insert(entity, volume);
+    } else {
+      let displacement = vel.map_or(Vector2::zeros(), |v| Vector2::new(v.x, v.y));
+      broadphase.update(entity, volume, displacement);
+    }
+  }
+
+  // collect overlapping pairs
+  pairs.pairs.clear();
+  broadphase.for_each_pair(&mut |a, b| {
+    pairs.pairs.push(if a < b { (a, b) } else { (b, a) });
+  });
+  pairs.pairs.sort_unstable();
+  pairs.pairs.dedup();
+}
+
+/* ---------------------------------------- */
+
+/// Cell size of the spatial hash, roughly the size of an asteroid.
+const SPATIAL_HASH_CELL_SIZE: f32 = 0.25;
+
+/// Swaps the tree for a toroidal spatial hash, which also pairs asteroids
+/// across the edges of the world, whenever `use_spatial_hash` is toggled.
+/// Every collider is inserted into the new broadphase.
+pub fn broadphase_toggle_system(
+  game_state: Res<GameState>,
+  mut using_spatial_hash: Local<bool>,
+  mut broadphase: ResMut<BroadphaseResource>,
+  data: Query<(Entity, &components::Collider, &components::Position)>
+) {
+  if game_state.use_spatial_hash == *using_spatial_hash { return; }
+  *using_spatial_hash = game_state.use_spatial_hash;
+
+  *broadphase = if game_state.use_spatial_hash {
+    BroadphaseResource::with(SpatialHash::new(world_bounds(), SPATIAL_HASH_CELL_SIZE))
+  } else {
+    BroadphaseResource::new()
+  };
+
+  for (entity, collider, pos) in data {
+    broadphase.broadphase.insert(entity, world_volume(collider, pos));
+  }
+}
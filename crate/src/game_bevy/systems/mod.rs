@@ -2,5 +2,6 @@ pub mod render_system;
 pub mod physics_system;
 pub mod event_system;
 pub mod player_control_system;
+pub mod broadphase_system;
 
 pub use physics_system::*;
\ No newline at end of file
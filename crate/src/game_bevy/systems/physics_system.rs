@@ -1,8 +1,17 @@
 use bevy_ecs::prelude::*;
+use crate::bvh::aabb::AABB;
 use crate::game_bevy::components;
 
 /* ---------------------------------------- */
 
+/// Positions wrap around this square, so the world is a torus.
+pub fn world_bounds() -> AABB {
+  return AABB::new(
+    nalgebra::Vector2::new(-1.0, -1.0),
+    nalgebra::Vector2::new( 1.0,  1.0)
+  );
+}
+
 type PhysicsSystemData<'a> = (
   &'a mut components::Position,
   &'a     components::Velocity
@@ -14,13 +23,17 @@ type PhysicsSystemData<'a> = (
 pub fn physics_system(
   data: Query<PhysicsSystemData>
 ) {
+  let bounds = world_bounds();
+  let (lower, upper) = (bounds.lower_bound, bounds.upper_bound);
+  let size = upper - lower;
+
   for (mut pos, vel) in data {
     pos.pos.0 += vel.x;
     pos.pos.1 += vel.y;
 
-    while pos.pos.0 < -1.0 { pos.pos.0 += 2.0; }
-    while pos.pos.0 >  1.0 { pos.pos.0 -= 2.0; }
-    while pos.pos.1 < -1.0 { pos.pos.1 += 2.0; }
-    while pos.pos.1 >  1.0 { pos.pos.1 -= 2.0; }
+    while pos.pos.0 < lower.x { pos.pos.0 += size.x; }
+    while pos.pos.0 > upper.x { pos.pos.0 -= size.x; }
+    while pos.pos.1 < lower.y { pos.pos.1 += size.y; }
+    while pos.pos.1 > upper.y { pos.pos.1 -= size.y; }
   }
 }
\ No newline at end of file
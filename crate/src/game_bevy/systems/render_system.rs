@@ -2,8 +2,11 @@ use std::rc::Rc;
 use glow::{Context, HasContext};
 use bevy_ecs::prelude::*;
 
+use crate::bvh::aabb::AABB;
 use crate::game_bevy::components;
-use crate::{graphics::{batch_poly_renderer::BatchPolyRenderer, shader::Shader}};
+use crate::game_bevy::resources::game_state::GameState;
+use crate::game_bevy::systems::broadphase_system::BroadphaseResource;
+use crate::{graphics::{batch_poly_renderer::BatchPolyRenderer, shader::{GlslType, Shader}}};
 
 /* ---------------------------------- */
 
@@ -12,19 +15,52 @@ use crate::{graphics::{batch_poly_renderer::BatchPolyRenderer, shader::Shader}};
 
 pub struct RenderResource {
   pub gl: Rc<Context>,
+  pub shader: Shader,
   pub shape_renderer: BatchPolyRenderer,
-  pub aabb_renderer: BatchPolyRenderer
+  pub aabb_renderer: BatchPolyRenderer,
+  pub broadphase_renderer: BatchPolyRenderer
 }
 
+/// colour of shapes and their bounding boxes
+const SHAPE_COLOR: [f32; 4] = [0.2, 0.25, 0.3, 0.4];
+
+/// number of points used to draw a full circle
+const CIRCLE_SEGMENTS: usize = 24;
+
 // TODO where does this code belong?
-fn activate_shaders(gl: Rc<Context>) {
+fn activate_shaders(gl: Rc<Context>) -> Shader {
     /* ---- compile shaders ---- */
 
     let vert_src = include_str!("../../../shaders/basic/basic.vert");
     let frag_src = include_str!("../../../shaders/basic/basic.frag");
-    let shader = Shader::build(&gl, vert_src, frag_src, &[]).unwrap();
+    let uniforms = [("u_color".to_string(), GlslType::Vec4)];
+    let shader = Shader::build(&gl, vert_src, frag_src, &uniforms).unwrap();
 
     shader.activate(&gl);
+    return shader;
+}
+
+/// appends the corners of `volume` to the buffers as a single closed shape
+fn push_aabb(vbo_data: &mut Vec<f32>, ebo_data: &mut Vec<u32>, max_vbo_idx: &mut u32, volume: &AABB) {
+  let (lower, upper) = (volume.lower_bound, volume.upper_bound);
+
+  // bottom left, top left, top right, bottom right
+  for (x, y) in [(lower.x, lower.y), (lower.x, upper.y), (upper.x, upper.y), (upper.x, lower.y)] {
+    vbo_data.push(x);
+    vbo_data.push(y);
+    ebo_data.push(*max_vbo_idx);
+    *max_vbo_idx += 1;
+  }
+
+  // end shape
+  ebo_data.push(u32::MAX); // PRIMITIVE_RESTART_FIXED_INDEX
+}
+
+/// Colour of broadphase volumes at `depth` out of `num_levels`, fading from
+/// opaque orange at the root to faint cyan at the leaves.
+fn depth_color(depth: usize, num_levels: usize) -> [f32; 4] {
+  let t = depth as f32 / num_levels.max(1) as f32;
+  return [1.0 - 0.8 * t, 0.6 + 0.3 * t, 0.2 + 0.8 * t, 0.9 - 0.7 * t];
 }
 
 impl RenderResource {
@@ -32,13 +68,16 @@ impl RenderResource {
     // batch renderers
     let shape_renderer = BatchPolyRenderer::build(Rc::clone(&gl));
     let aabb_renderer = BatchPolyRenderer::build(Rc::clone(&gl));
+    let broadphase_renderer = BatchPolyRenderer::build(Rc::clone(&gl));
 
-    activate_shaders(Rc::clone(&gl));
+    let shader = activate_shaders(Rc::clone(&gl));
 
     return RenderResource {
       gl,
+      shader,
       shape_renderer,
-      aabb_renderer
+      aabb_renderer,
+      broadphase_renderer
     }
   }
 
@@ -57,12 +96,16 @@ impl RenderResource {
     }
   }
 
+  fn set_color(&mut self, [r, g, b, a]: [f32; 4]) {
+    self.shader.set_uniform_vec4(&self.gl, "u_color", r, g, b, a);
+  }
+
   fn render_end(&self) {
     // TODO double buffering only for desktop target, as WebGL does it automatically
     // self.window.gl_swap_window();
   }
 
-  fn render_shapes(&self, data: &Query<RenderData>) {
+  fn render_shapes(&mut self, data: &Query<RenderData>) {
     // TODO (Ben @ 2024/08/25) optimize by reusing these vectors?
     let mut vbo_data = Vec::<f32>::new();
     let mut ebo_data = Vec::<u32>::new();
@@ -70,22 +113,26 @@ impl RenderResource {
     let mut max_vbo_idx: u32 = 0;
     let mut num_shapes = 0;
     for (pos, geom, _) in data {
-      for col in geom.shape.points.column_iter() {
-        vbo_data.push(pos.pos.0 + col.x);
-        vbo_data.push(pos.pos.1 + col.y);
-
-        ebo_data.push(max_vbo_idx);
-        max_vbo_idx += 1;
+      // compound shapes are drawn as one outline per part
+      for outline in geom.shape.outlines(CIRCLE_SEGMENTS) {
+        for point in outline {
+          vbo_data.push(pos.pos.0 + point.x);
+          vbo_data.push(pos.pos.1 + point.y);
+
+          ebo_data.push(max_vbo_idx);
+          max_vbo_idx += 1;
+        }
+
+        ebo_data.push(u32::MAX); // PRIMITIVE_RESTART_FIXED_INDEX
+        num_shapes += 1;
       }
-
-      ebo_data.push(u32::MAX); // PRIMITIVE_RESTART_FIXED_INDEX
-      num_shapes += 1;
     }
 
+    self.set_color(SHAPE_COLOR);
     self.shape_renderer.render(vbo_data, ebo_data, num_shapes, max_vbo_idx, true, true);
   }
 
-  fn render_aabb(&self, data: &Query<RenderData>) {
+  fn render_aabb(&mut self, data: &Query<RenderData>) {
     // TODO (Ben @ 2024/08/25) optimize by reusing these vectors?
     let mut vbo_data = Vec::<f32>::new();
     let mut ebo_data = Vec::<u32>::new();
@@ -93,37 +140,49 @@ impl RenderResource {
     let mut max_vbo_idx: u32 = 0;
     let mut num_shapes = 0;
     for (pos, _, collider) in data {
-      // bottom left
-      vbo_data.push(pos.pos.0 + collider.volume.lower_bound.x);
-      vbo_data.push(pos.pos.1 + collider.volume.lower_bound.y);
-      ebo_data.push(max_vbo_idx);
-      max_vbo_idx += 1;
-
-      // top left
-      vbo_data.push(pos.pos.0 + collider.volume.lower_bound.x);
-      vbo_data.push(pos.pos.1 + collider.volume.upper_bound.y);
-      ebo_data.push(max_vbo_idx);
-      max_vbo_idx += 1;
-
-      // top right
-      vbo_data.push(pos.pos.0 + collider.volume.upper_bound.x);
-      vbo_data.push(pos.pos.1 + collider.volume.upper_bound.y);
-      ebo_data.push(max_vbo_idx);
-      max_vbo_idx += 1;
-
-      // bottom right
-      vbo_data.push(pos.pos.0 + collider.volume.upper_bound.x);
-      vbo_data.push(pos.pos.1 + collider.volume.lower_bound.y);
-      ebo_data.push(max_vbo_idx);
-      max_vbo_idx += 1;
-
-      // end shape
-      ebo_data.push(u32::MAX); // PRIMITIVE_RESTART_FIXED_INDEX
+      let offset = nalgebra::Vector2::new(pos.pos.0, pos.pos.1);
+      push_aabb(&mut vbo_data, &mut ebo_data, &mut max_vbo_idx, &collider.volume.translate(offset));
       num_shapes += 1;
     }
 
+    self.set_color(SHAPE_COLOR);
     self.aabb_renderer.render(vbo_data, ebo_data, num_shapes, max_vbo_idx, false, true);
   }
+
+  /// Draws the volumes of the broadphase structure as outlines, e.g. the
+  /// internal nodes of a tree, with one draw call per depth so that each
+  /// level can be given its own colour.
+  fn render_broadphase(&mut self, broadphase: &BroadphaseResource) {
+    let mut levels = Vec::<Vec<AABB>>::new();
+    broadphase.broadphase.for_each_debug_volume(&mut |volume, depth| {
+      if levels.len() <= depth { levels.resize(depth + 1, vec![]); }
+      levels[depth].push(*volume);
+    });
+
+    unsafe {
+      self.gl.enable(glow::BLEND);
+      self.gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+    }
+
+    let num_levels = levels.len();
+    for (depth, volumes) in levels.into_iter().enumerate() {
+      let mut vbo_data = Vec::<f32>::new();
+      let mut ebo_data = Vec::<u32>::new();
+      let mut max_vbo_idx: u32 = 0;
+
+      for volume in &volumes {
+        push_aabb(&mut vbo_data, &mut ebo_data, &mut max_vbo_idx, volume);
+      }
+
+      self.set_color(depth_color(depth, num_levels));
+      let num_shapes = volumes.len() as u32;
+      self.broadphase_renderer.render(vbo_data, ebo_data, num_shapes, max_vbo_idx, false, true);
+    }
+
+    unsafe {
+      self.gl.disable(glow::BLEND);
+    }
+  }
 }
 
 type RenderData<'a> = (
@@ -137,10 +196,15 @@ type RenderData<'a> = (
 // https://bevy-cheatbook.github.io/programming/non-send.html
 pub fn render_system(
   data: Query<RenderData>,
-  renderer: NonSend<RenderResource>
+  game_state: Res<GameState>,
+  broadphase: Res<BroadphaseResource>,
+  mut renderer: NonSendMut<RenderResource>
 ) {
   renderer.render_begin();
   renderer.render_shapes(&data);
   renderer.render_aabb(&data);
+  if game_state.show_broadphase {
+    renderer.render_broadphase(&broadphase);
+  }
   renderer.render_end();
 }
\ No newline at end of file
@@ -1,14 +1,77 @@
 extern crate nalgebra as nalg;
 use std::f32::consts::PI;
 
-use nalg::OMatrix;
+use nalg::{OMatrix, Vector2};
 
 type PointMatrix<C> = OMatrix<f32, nalg::U2, C>;
 
+/// A convex polygon whose points are stored as the columns of a matrix, in
+/// counter-clockwise order, with no repeated or collinear points.
+#[derive(Debug, Clone)]
 pub struct ConvexPoly {
   pub points: PointMatrix<nalg::Dyn>
 }
 
+/// A reason why a list of points does not describe a `ConvexPoly`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvexPolyError {
+  /// Fewer than three points were given.
+  TooFewPoints { count: usize },
+  /// A coordinate of the point at this index is infinite or NaN.
+  NonFinite { index: usize },
+  /// Every point lies on a single line, so the polygon has no area.
+  Degenerate,
+  /// The point at this index repeats its predecessor, or lies on the line
+  /// through its neighbours.
+  Collinear { index: usize },
+  /// The polygon turns clockwise at the point at this index, so it is either
+  /// not convex or wound clockwise.
+  Reflex { index: usize },
+  /// Every turn is counter-clockwise, but the boundary winds around more than
+  /// once, as in a pentagram.
+  SelfIntersecting,
+}
+
+impl std::fmt::Display for ConvexPolyError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ConvexPolyError::TooFewPoints { count } =>
+        write!(f, "a polygon needs at least 3 points, but {count} were given"),
+      ConvexPolyError::NonFinite { index } =>
+        write!(f, "point {index} has a non-finite coordinate"),
+      ConvexPolyError::Degenerate =>
+        write!(f, "all points are collinear, so the polygon has no area"),
+      ConvexPolyError::Collinear { index } =>
+        write!(f, "point {index} is collinear with its neighbours"),
+      ConvexPolyError::Reflex { index } =>
+        write!(f, "polygon turns clockwise at point {index}, so it is not convex and counter-clockwise"),
+      ConvexPolyError::SelfIntersecting =>
+        write!(f, "polygon winds around more than once"),
+    }
+  }
+}
+
+impl std::error::Error for ConvexPolyError {}
+
+/// Mass data of a rigid body with uniform density.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassProperties {
+  pub mass: f32,
+  /// center of mass, in the same frame as the shape's points
+  pub center: Vector2<f32>,
+  /// moment of inertia about the center of mass
+  pub inertia: f32
+}
+
+/// Twice the signed area of the triangle `abc`, which is positive when the
+/// points turn counter-clockwise.  Computed in double precision so that the
+/// sign is exact for all but the most extreme single-precision inputs.
+fn orient(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>) -> f64 {
+  let (abx, aby) = (b.x as f64 - a.x as f64, b.y as f64 - a.y as f64);
+  let (acx, acy) = (c.x as f64 - a.x as f64, c.y as f64 - a.y as f64);
+  abx * acy - aby * acx
+}
+
 impl ConvexPoly {
   // construct a regular polygon
   pub fn regular(
@@ -17,7 +80,7 @@ impl ConvexPoly {
   ) -> ConvexPoly {
     assert!(num_points > 2);
 
-    let mut data = Vec::<f32>::new(); 
+    let mut data = Vec::<f32>::new();
     let angle = 2.0 * PI / f32::from(num_points);
 
     for n in 0..num_points {
@@ -30,4 +93,165 @@ impl ConvexPoly {
 
     ConvexPoly { points: PointMatrix::<nalg::Dyn>::from_vec(data) }
   }
-}
\ No newline at end of file
+
+  /// Constructs the convex hull of a set of points using Andrew's monotone
+  /// chain algorithm.  Points inside the hull, repeated points and points in
+  /// the middle of a hull edge are discarded, and the remaining points are
+  /// wound counter-clockwise starting from the lowest, leftmost point.
+  pub fn from_points<I>(points: I) -> Result<ConvexPoly, ConvexPolyError>
+  where I: IntoIterator<Item = Vector2<f32>> {
+    let mut points: Vec<Vector2<f32>> = points.into_iter().collect();
+
+    if let Some(index) = points.iter().position(|p| !p.x.is_finite() || !p.y.is_finite()) {
+      return Err(ConvexPolyError::NonFinite { index });
+    }
+    if points.len() < 3 {
+      return Err(ConvexPolyError::TooFewPoints { count: points.len() });
+    }
+
+    // sort lexicographically, so the chains sweep from left to right
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+
+    // appends a point to a chain, first popping any points which would
+    // not make a strict counter-clockwise turn, including collinear ones
+    let push = |chain: &mut Vec<Vector2<f32>>, p: Vector2<f32>| {
+      while chain.len() >= 2 && orient(chain[chain.len() - 2], chain[chain.len() - 1], p) <= 0.0 {
+        chain.pop();
+      }
+      chain.push(p);
+    };
+
+    let mut lower = Vec::new();
+    for &p in points.iter() { push(&mut lower, p); }
+    let mut upper = Vec::new();
+    for &p in points.iter().rev() { push(&mut upper, p); }
+
+    // the last point of each chain is the first point of the other
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+
+    if lower.len() < 3 { return Err(ConvexPolyError::Degenerate); }
+    Ok(ConvexPoly { points: PointMatrix::<nalg::Dyn>::from_columns(&lower) })
+  }
+
+  /// Constructs a polygon from points which should already describe a convex
+  /// polygon wound counter-clockwise, without repeated or collinear points.
+  /// Returns an error describing the first problem found otherwise.
+  pub fn try_new<I>(points: I) -> Result<ConvexPoly, ConvexPolyError>
+  where I: IntoIterator<Item = Vector2<f32>> {
+    let points: Vec<Vector2<f32>> = points.into_iter().collect();
+    let n = points.len();
+
+    if let Some(index) = points.iter().position(|p| !p.x.is_finite() || !p.y.is_finite()) {
+      return Err(ConvexPolyError::NonFinite { index });
+    }
+    if n < 3 {
+      return Err(ConvexPolyError::TooFewPoints { count: n });
+    }
+
+    // every turn must be strictly counter-clockwise
+    let mut total_turn = 0.0;
+    for i in 0..n {
+      let (prev, curr, next) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+      let turn = orient(prev, curr, next);
+      if turn == 0.0 { return Err(ConvexPolyError::Collinear { index: i }); }
+      if turn < 0.0 { return Err(ConvexPolyError::Reflex { index: i }); }
+
+      // exterior angle at this point
+      let (d1, d2) = (curr - prev, next - curr);
+      total_turn += d1.perp(&d2).atan2(d1.dot(&d2));
+    }
+
+    // a simple convex polygon turns through exactly one full revolution
+    if total_turn > 3.0 * PI {
+      return Err(ConvexPolyError::SelfIntersecting);
+    }
+
+    Ok(ConvexPoly { points: PointMatrix::<nalg::Dyn>::from_columns(&points) })
+  }
+
+  /// Returns the area enclosed by the polygon, which is positive since the
+  /// points are wound counter-clockwise.
+  pub fn area(&self) -> f32 {
+    let origin = self.point(0);
+    let mut area = 0.0;
+    for i in 1..self.num_points() - 1 {
+      let (e1, e2) = (self.point(i) - origin, self.point(i + 1) - origin);
+      area += 0.5 * e1.perp(&e2);
+    }
+    area
+  }
+
+  /// Returns the center of area of the polygon, i.e. its center of mass under
+  /// uniform density, as the area-weighted mean of a triangle fan's centroids.
+  pub fn centroid(&self) -> Vector2<f32> {
+    // fan out from a point inside the polygon, rather than from the
+    // origin, so that precision does not depend on where the polygon is
+    let reference = self.points.column_mean();
+
+    let mut area = 0.0;
+    let mut weighted = Vector2::zeros();
+    for i in 0..self.num_points() {
+      let (e1, e2) = self.fan_edges(reference, i);
+      let triangle_area = 0.5 * e1.perp(&e2);
+      area += triangle_area;
+      // centroid of the triangle (reference, p1, p2), relative to reference
+      weighted += triangle_area * (e1 + e2) / 3.0;
+    }
+
+    reference + weighted / area
+  }
+
+  /// Returns the moment of inertia about the centroid for the given density.
+  /// The polygon is split into a fan of triangles `(c, p1, p2)` about the
+  /// centroid `c`, each contributing `density * D / 12 * (e1·e1 + e1·e2 + e2·e2)`
+  /// where `e1 = p1 - c`, `e2 = p2 - c` and `D = e1 × e2` is twice its area.
+  pub fn moment_of_inertia(&self, density: f32) -> f32 {
+    let centroid = self.centroid();
+
+    let mut inertia = 0.0;
+    for i in 0..self.num_points() {
+      let (e1, e2) = self.fan_edges(centroid, i);
+      inertia += e1.perp(&e2) / 12.0 * (e1.dot(&e1) + e1.dot(&e2) + e2.dot(&e2));
+    }
+
+    density * inertia
+  }
+
+  /// computes the mass, center of mass and inertia for the given density
+  pub fn mass_properties(&self, density: f32) -> MassProperties {
+    MassProperties {
+      mass    : density * self.area(),
+      center  : self.centroid(),
+      inertia : self.moment_of_inertia(density)
+    }
+  }
+
+  /// Shifts the points so that the centroid lies at the origin, returning the
+  /// previous centroid so the owner's position can be moved to compensate.
+  pub fn recenter(&mut self) -> Vector2<f32> {
+    let centroid = self.centroid();
+    for mut col in self.points.column_iter_mut() {
+      col -= centroid;
+    }
+    centroid
+  }
+
+  /// edges from `apex` to the ends of the `i`th side of the polygon
+  fn fan_edges(&self, apex: Vector2<f32>, i: usize) -> (Vector2<f32>, Vector2<f32>) {
+    let j = (i + 1) % self.num_points();
+    (self.point(i) - apex, self.point(j) - apex)
+  }
+
+  /// number of points on the boundary
+  pub fn num_points(&self) -> usize {
+    self.points.ncols()
+  }
+
+  /// the point at index `i`, in counter-clockwise order
+  pub fn point(&self, i: usize) -> Vector2<f32> {
+    self.points.column(i).into()
+  }
+}
@@ -0,0 +1,150 @@
+//! Expanding Polytope Algorithm, which finds how deeply two overlapping convex
+//! shapes penetrate.  Starting from the simplex left by GJK, the polytope is
+//! grown towards the boundary of the Minkowski difference `A - B` until the
+//! edge closest to the origin is part of that boundary.
+
+extern crate nalgebra as nalg;
+
+use nalg::{Isometry2, Vector2};
+
+use crate::geom::SupportMap;
+use crate::geom::gjk::{Simplex, SimplexVertex};
+
+/// Maximum number of support points added to the polytope.
+const MAX_ITERATIONS: usize = 64;
+
+/// Relative tolerance at which an edge is accepted as part of the boundary.
+const TOLERANCE: f32 = 1.0e-5;
+
+/// How far two overlapping shapes penetrate each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Penetration {
+  /// distance shape B must move along `normal` to separate the shapes
+  pub depth: f32,
+  /// unit direction pointing from shape A towards shape B
+  pub normal: Vector2<f32>,
+  /// deepest point of shape A inside shape B, in world coordinates
+  pub point_a: Vector2<f32>,
+  /// deepest point of shape B inside shape A, in world coordinates
+  pub point_b: Vector2<f32>
+}
+
+/// An edge of the polytope, from vertex `index` to its successor.
+struct Edge {
+  index: usize,
+  normal: Vector2<f32>,
+  distance: f32
+}
+
+/// Computes the penetration of two overlapping shapes from the terminal
+/// simplex of a GJK query between them.  The simplex may be a point or a
+/// segment when the shapes only touch, in which case it is first extended to
+/// a triangle.  Returns `None` only if the Minkowski difference has no area,
+/// i.e. both shapes are degenerate.
+pub fn penetration<A, B>(
+  a: &A,
+  transform_a: &Isometry2<f32>,
+  b: &B,
+  transform_b: &Isometry2<f32>,
+  simplex: &Simplex
+) -> Option<Penetration>
+where A: SupportMap + ?Sized, B: SupportMap + ?Sized {
+  let support = |dir: Vector2<f32>| SimplexVertex::new(a, transform_a, b, transform_b, &dir);
+  let mut polytope = initial_polytope(simplex, support)?;
+
+  let mut closest = closest_edge(&polytope);
+  for _ in 0..MAX_ITERATIONS {
+    let vertex = support(closest.normal);
+
+    // the edge is on the boundary if nothing lies further out beyond it
+    let gain = vertex.w.dot(&closest.normal) - closest.distance;
+    let repeated = polytope.iter().any(|v| v.a == vertex.a && v.b == vertex.b);
+    if repeated || gain <= TOLERANCE * closest.distance.abs().max(1.0) {
+      break;
+    }
+
+    polytope.insert(closest.index + 1, vertex);
+    closest = closest_edge(&polytope);
+  }
+
+  // project the origin onto the closest edge to find the witness points
+  let v1 = &polytope[closest.index];
+  let v2 = &polytope[(closest.index + 1) % polytope.len()];
+  let e12 = v2.w - v1.w;
+  let t = (-v1.w.dot(&e12) / e12.norm_squared()).clamp(0.0, 1.0);
+
+  Some(Penetration {
+    depth: closest.distance.max(0.0),
+    normal: closest.normal,
+    point_a: v1.a + t * (v2.a - v1.a),
+    point_b: v1.b + t * (v2.b - v1.b)
+  })
+}
+
+/// Builds a counter-clockwise triangle from the GJK simplex, adding support
+/// points when the shapes merely touch and the simplex is a point or segment.
+fn initial_polytope<F>(simplex: &Simplex, support: F) -> Option<Vec<SimplexVertex>>
+where F: Fn(Vector2<f32>) -> SimplexVertex {
+  let mut points: Vec<SimplexVertex> = Vec::with_capacity(8);
+  for &vertex in simplex.vertices() {
+    if points.iter().all(|p| p.w != vertex.w) { points.push(vertex); }
+  }
+
+  // a flat triangle is no better than its longest side
+  if points.len() == 3 && triangle_area(&points) == 0.0 {
+    let longest = (0..3)
+      .max_by(|&i, &j| {
+        let (ei, ej) = (points[(i + 1) % 3].w - points[i].w, points[(j + 1) % 3].w - points[j].w);
+        ei.norm_squared().total_cmp(&ej.norm_squared())
+      })
+      .unwrap();
+    points = vec![points[longest], points[(longest + 1) % 3]];
+  }
+
+  // a single point: try the axes until a second, distinct point turns up
+  if points.len() == 1 {
+    let axes = [Vector2::x(), -Vector2::x(), Vector2::y(), -Vector2::y()];
+    let other = axes.iter().map(|&dir| support(dir)).find(|v| v.w != points[0].w)?;
+    points.push(other);
+  }
+
+  // a segment: take whichever support point perpendicular to it lies further
+  // from its line, so that the triangle has as much area as possible
+  if points.len() == 2 {
+    let e = points[1].w - points[0].w;
+    let normal = Vector2::new(-e.y, e.x);
+    let (left, right) = (support(normal), support(-normal));
+    let height = |v: &SimplexVertex| normal.dot(&(v.w - points[0].w)).abs();
+    points.push(if height(&left) >= height(&right) { left } else { right });
+  }
+
+  let area = triangle_area(&points);
+  if area == 0.0 { return None; }
+  if area < 0.0 { points.swap(1, 2); }
+  Some(points)
+}
+
+/// twice the signed area of a triangle of simplex vertices
+fn triangle_area(points: &[SimplexVertex]) -> f32 {
+  (points[1].w - points[0].w).perp(&(points[2].w - points[0].w))
+}
+
+/// finds the edge of a counter-clockwise polytope nearest to the origin
+fn closest_edge(polytope: &[SimplexVertex]) -> Edge {
+  let n = polytope.len();
+  let mut closest = Edge { index: 0, normal: Vector2::zeros(), distance: f32::INFINITY };
+  for i in 0..n {
+    let (w1, w2) = (polytope[i].w, polytope[(i + 1) % n].w);
+    let e = w2 - w1;
+    let length = e.norm();
+    if length == 0.0 { continue; }
+
+    // outward normal, which is to the right of a counter-clockwise edge
+    let normal = Vector2::new(e.y, -e.x) / length;
+    let distance = normal.dot(&w1);
+    if distance < closest.distance {
+      closest = Edge { index: i, normal, distance };
+    }
+  }
+  closest
+}
@@ -0,0 +1,279 @@
+//! Gilbert–Johnson–Keerthi distance queries between convex shapes, following
+//! the structure of `b2Distance` from Box2D.  GJK searches the Minkowski
+//! difference `A - B` for the point closest to the origin, using a simplex of
+//! up to three support points; the shapes overlap when the origin is inside.
+
+extern crate nalgebra as nalg;
+
+use nalg::{Isometry2, Vector2};
+
+use crate::geom::SupportMap;
+
+/// Maximum number of support points evaluated by a single query.
+const MAX_ITERATIONS: usize = 32;
+
+/// Shapes closer than this are considered to be touching.
+const TOLERANCE: f32 = 1.0e-6;
+
+/// A vertex of the simplex, i.e. a point `w = a - b` of the Minkowski
+/// difference along with the support points of each shape which produced it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimplexVertex {
+  /// support point of shape A, in world coordinates
+  pub a: Vector2<f32>,
+  /// support point of shape B, in world coordinates
+  pub b: Vector2<f32>,
+  /// the point `a - b` of the Minkowski difference
+  pub w: Vector2<f32>,
+  /// barycentric coordinate of the closest point to the origin
+  pub lambda: f32
+}
+
+impl SimplexVertex {
+  pub(crate) fn new<A, B>(a: &A, transform_a: &Isometry2<f32>, b: &B, transform_b: &Isometry2<f32>, dir: &Vector2<f32>) -> Self
+  where A: SupportMap + ?Sized, B: SupportMap + ?Sized {
+    let a = a.support_point(transform_a, dir);
+    let b = b.support_point(transform_b, &-dir);
+    SimplexVertex { a, b, w: a - b, lambda: 1.0 }
+  }
+}
+
+/// A point, segment or triangle in the Minkowski difference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simplex {
+  vertices: [SimplexVertex; 3],
+  count: usize
+}
+
+impl Simplex {
+  /// the vertices of the simplex, of which there are between one and three
+  pub fn vertices(&self) -> &[SimplexVertex] {
+    &self.vertices[..self.count]
+  }
+
+  /// the point of the simplex closest to the origin
+  fn closest_point(&self) -> Vector2<f32> {
+    self.vertices().iter().map(|v| v.lambda * v.w).sum()
+  }
+
+  /// the closest points on each shape, as (point on A, point on B)
+  fn witness_points(&self) -> (Vector2<f32>, Vector2<f32>) {
+    let a = self.vertices().iter().map(|v| v.lambda * v.a).sum();
+    let b = self.vertices().iter().map(|v| v.lambda * v.b).sum();
+    (a, b)
+  }
+
+  /// Direction in which to search for the next support point.  For a segment,
+  /// the perpendicular facing the origin is used rather than the negated
+  /// closest point, as it is more accurate when the origin is nearly on it.
+  fn search_direction(&self) -> Vector2<f32> {
+    match self.count {
+      1 => -self.vertices[0].w,
+      2 => {
+        let (w1, w2) = (self.vertices[0].w, self.vertices[1].w);
+        let e12 = w2 - w1;
+        if e12.perp(&-w1) > 0.0 {
+          // origin is to the left of the segment
+          Vector2::new(-e12.y, e12.x)
+        } else {
+          Vector2::new(e12.y, -e12.x)
+        }
+      }
+      _ => unreachable!("a full simplex contains the origin")
+    }
+  }
+
+  /// Reduces the simplex to the smallest subset whose convex hull contains
+  /// the point closest to the origin, and sets the barycentric coordinates.
+  fn solve(&mut self) {
+    match self.count {
+      1 => { self.vertices[0].lambda = 1.0; }
+      2 => self.solve2(),
+      3 => self.solve3(),
+      _ => unreachable!("simplex has between one and three vertices")
+    }
+  }
+
+  fn solve2(&mut self) {
+    let (w1, w2) = (self.vertices[0].w, self.vertices[1].w);
+    let e12 = w2 - w1;
+
+    // region of w1
+    let d12_2 = -w1.dot(&e12);
+    if d12_2 <= 0.0 {
+      self.vertices[0].lambda = 1.0;
+      self.count = 1;
+      return;
+    }
+
+    // region of w2
+    let d12_1 = w2.dot(&e12);
+    if d12_1 <= 0.0 {
+      self.vertices[0] = self.vertices[1];
+      self.vertices[0].lambda = 1.0;
+      self.count = 1;
+      return;
+    }
+
+    // interior of the segment
+    let inv_d12 = 1.0 / (d12_1 + d12_2);
+    self.vertices[0].lambda = d12_1 * inv_d12;
+    self.vertices[1].lambda = d12_2 * inv_d12;
+  }
+
+  fn solve3(&mut self) {
+    let (w1, w2, w3) = (self.vertices[0].w, self.vertices[1].w, self.vertices[2].w);
+
+    // edge regions, where dij_k is the (unnormalized) barycentric
+    // coordinate of the k-th endpoint of edge ij
+    let e12 = w2 - w1;
+    let (d12_1, d12_2) = (w2.dot(&e12), -w1.dot(&e12));
+    let e13 = w3 - w1;
+    let (d13_1, d13_2) = (w3.dot(&e13), -w1.dot(&e13));
+    let e23 = w3 - w2;
+    let (d23_1, d23_2) = (w3.dot(&e23), -w2.dot(&e23));
+
+    // triangle regions
+    let n123 = e12.perp(&e13);
+    let d123_1 = n123 * w2.perp(&w3);
+    let d123_2 = n123 * w3.perp(&w1);
+    let d123_3 = n123 * w1.perp(&w2);
+
+    // region of w1
+    if d12_2 <= 0.0 && d13_2 <= 0.0 {
+      self.vertices[0].lambda = 1.0;
+      self.count = 1;
+      return;
+    }
+
+    // edge 12
+    if d12_1 > 0.0 && d12_2 > 0.0 && d123_3 <= 0.0 {
+      let inv_d12 = 1.0 / (d12_1 + d12_2);
+      self.vertices[0].lambda = d12_1 * inv_d12;
+      self.vertices[1].lambda = d12_2 * inv_d12;
+      self.count = 2;
+      return;
+    }
+
+    // edge 13
+    if d13_1 > 0.0 && d13_2 > 0.0 && d123_2 <= 0.0 {
+      let inv_d13 = 1.0 / (d13_1 + d13_2);
+      self.vertices[0].lambda = d13_1 * inv_d13;
+      self.vertices[2].lambda = d13_2 * inv_d13;
+      self.vertices[1] = self.vertices[2];
+      self.count = 2;
+      return;
+    }
+
+    // region of w2
+    if d12_1 <= 0.0 && d23_2 <= 0.0 {
+      self.vertices[0] = self.vertices[1];
+      self.vertices[0].lambda = 1.0;
+      self.count = 1;
+      return;
+    }
+
+    // region of w3
+    if d13_1 <= 0.0 && d23_1 <= 0.0 {
+      self.vertices[0] = self.vertices[2];
+      self.vertices[0].lambda = 1.0;
+      self.count = 1;
+      return;
+    }
+
+    // edge 23
+    if d23_1 > 0.0 && d23_2 > 0.0 && d123_1 <= 0.0 {
+      let inv_d23 = 1.0 / (d23_1 + d23_2);
+      self.vertices[1].lambda = d23_1 * inv_d23;
+      self.vertices[2].lambda = d23_2 * inv_d23;
+      self.vertices[0] = self.vertices[2];
+      self.count = 2;
+      return;
+    }
+
+    // the origin is inside the triangle
+    let inv_d123 = 1.0 / (d123_1 + d123_2 + d123_3);
+    self.vertices[0].lambda = d123_1 * inv_d123;
+    self.vertices[1].lambda = d123_2 * inv_d123;
+    self.vertices[2].lambda = d123_3 * inv_d123;
+  }
+}
+
+/// Result of a GJK query between two shapes.
+#[derive(Debug, Clone)]
+pub struct GjkResult {
+  /// point of shape A closest to shape B, in world coordinates
+  pub point_a: Vector2<f32>,
+  /// point of shape B closest to shape A, in world coordinates
+  pub point_b: Vector2<f32>,
+  /// distance between the shapes, which is zero when they intersect
+  pub distance: f32,
+  /// whether the shapes overlap or touch
+  pub intersecting: bool,
+  /// final simplex, which encloses the origin when the shapes overlap
+  pub simplex: Simplex,
+  /// number of support points evaluated
+  pub iterations: usize
+}
+
+/// Computes the distance and closest points between shape `a` placed by
+/// `transform_a` and shape `b` placed by `transform_b`.  When the shapes
+/// intersect, the distance is zero and the closest points are unspecified.
+pub fn distance<A, B>(
+  a: &A,
+  transform_a: &Isometry2<f32>,
+  b: &B,
+  transform_b: &Isometry2<f32>
+) -> GjkResult
+where A: SupportMap + ?Sized, B: SupportMap + ?Sized {
+  // start from the support points facing each other
+  let mut dir = transform_b.translation.vector - transform_a.translation.vector;
+  if dir.norm_squared() < TOLERANCE * TOLERANCE { dir = Vector2::x(); }
+
+  let first = SimplexVertex::new(a, transform_a, b, transform_b, &dir);
+  let mut simplex = Simplex { vertices: [first; 3], count: 1 };
+  let mut intersecting = false;
+  let mut iterations = 1;
+
+  while iterations < MAX_ITERATIONS {
+    simplex.solve();
+
+    // a full simplex encloses the origin, so the shapes overlap
+    if simplex.count == 3 {
+      intersecting = true;
+      break;
+    }
+
+    // the origin lies on the simplex, so the shapes are touching
+    let v = simplex.closest_point();
+    if v.norm_squared() < TOLERANCE * TOLERANCE {
+      intersecting = true;
+      break;
+    }
+
+    let dir = simplex.search_direction();
+    if dir.norm_squared() < TOLERANCE * TOLERANCE {
+      intersecting = true;
+      break;
+    }
+
+    let vertex = SimplexVertex::new(a, transform_a, b, transform_b, &dir);
+    iterations += 1;
+
+    // stop once a support point repeats a vertex of the simplex, or makes no
+    // progress towards the origin, since the closest point is then found
+    let repeated = simplex.vertices().iter().any(|v| v.a == vertex.a && v.b == vertex.b);
+    let progress = v.norm_squared() - v.dot(&vertex.w);
+    if repeated || progress <= TOLERANCE * v.norm_squared() {
+      break;
+    }
+
+    simplex.vertices[simplex.count] = vertex;
+    simplex.count += 1;
+  }
+
+  let (point_a, point_b) = simplex.witness_points();
+  let distance = if intersecting { 0.0 } else { (point_b - point_a).norm() };
+
+  GjkResult { point_a, point_b, distance, intersecting, simplex, iterations }
+}
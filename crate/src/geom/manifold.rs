@@ -0,0 +1,248 @@
+//! Contact manifolds between convex polygons using the separating axis
+//! theorem, following `b2CollidePolygons` from Box2D.  The axis of least
+//! penetration selects a reference edge on one polygon; the most anti-parallel
+//! edge of the other polygon is then clipped against the reference edge's side
+//! planes, giving up to two contact points.
+
+extern crate nalgebra as nalg;
+
+use nalg::{Isometry2, Point2, Vector2};
+
+use crate::geom::ConvexPoly;
+
+/// Separation by which polygon B's edge must beat polygon A's before it is
+/// used as the reference edge, so the choice does not flip between frames.
+const REFERENCE_EDGE_TOLERANCE: f32 = 5.0e-4;
+
+/// Whether a contact feature is a vertex or an edge of its polygon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureType {
+  Vertex,
+  Face
+}
+
+/// The features of each polygon which produced a contact point.  Matching
+/// features between frames identify the same contact, for warm starting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContactFeature {
+  pub index_a: usize,
+  pub index_b: usize,
+  pub type_a: FeatureType,
+  pub type_b: FeatureType
+}
+
+impl ContactFeature {
+  /// the same contact, seen from the other polygon
+  fn flipped(self) -> ContactFeature {
+    ContactFeature {
+      index_a: self.index_b,
+      index_b: self.index_a,
+      type_a: self.type_b,
+      type_b: self.type_a
+    }
+  }
+}
+
+/// A point of contact between two polygons.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactPoint {
+  /// world position, midway between the two surfaces
+  pub point: Vector2<f32>,
+  /// signed distance between the surfaces, which is negative when they overlap
+  pub separation: f32,
+  pub id: ContactFeature
+}
+
+/// Up to two contact points between a pair of polygons, sharing one normal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifold {
+  /// unit direction pointing from polygon A towards polygon B
+  pub normal: Vector2<f32>,
+  points: [ContactPoint; 2],
+  count: usize
+}
+
+impl Manifold {
+  /// a manifold from one or two contact points
+  pub(crate) fn new(normal: Vector2<f32>, points: &[ContactPoint]) -> Manifold {
+    assert!(matches!(points.len(), 1 | 2));
+    let mut manifold = Manifold { normal, points: [points[0]; 2], count: points.len() };
+    manifold.points[..points.len()].copy_from_slice(points);
+    manifold
+  }
+
+  /// the contact points, of which there are one or two
+  pub fn points(&self) -> &[ContactPoint] {
+    &self.points[..self.count]
+  }
+}
+
+/// A vertex of the incident edge, tagged with the features that produced it.
+#[derive(Debug, Clone, Copy)]
+struct ClipVertex {
+  v: Vector2<f32>,
+  id: ContactFeature
+}
+
+/// Computes the contact manifold between polygon `a` placed by `transform_a`
+/// and polygon `b` placed by `transform_b`, or `None` if they are separated.
+pub fn collide_polygons(
+  a: &ConvexPoly,
+  transform_a: &Isometry2<f32>,
+  b: &ConvexPoly,
+  transform_b: &Isometry2<f32>
+) -> Option<Manifold> {
+  return collide_points(&world_points(a, transform_a), &world_points(b, transform_b));
+}
+
+/// Computes the contact manifold between two counter-clockwise convex polygons
+/// given by their world space vertices, or `None` if they are separated.
+pub(crate) fn collide_points(points_a: &[Vector2<f32>], points_b: &[Vector2<f32>]) -> Option<Manifold> {
+  let (edge_a, separation_a) = find_max_separation(points_a, points_b);
+  if separation_a > 0.0 { return None; }
+
+  let (edge_b, separation_b) = find_max_separation(points_b, points_a);
+  if separation_b > 0.0 { return None; }
+
+  // the reference polygon owns the edge along which the overlap is smallest,
+  // preferring A unless B is clearly better
+  let flip = separation_b > separation_a + REFERENCE_EDGE_TOLERANCE;
+  let (poly1, poly2, edge1) = if flip {
+    (points_b, points_a, edge_b)
+  } else {
+    (points_a, points_b, edge_a)
+  };
+
+  let incident = find_incident_edge(poly1, edge1, poly2);
+
+  let (i11, i12) = (edge1, (edge1 + 1) % poly1.len());
+  let (v11, v12) = (poly1[i11], poly1[i12]);
+  let tangent = (v12 - v11).normalize();
+  let normal = edge_normal(tangent);
+
+  let front_offset = normal.dot(&v11);
+  let side_offset1 = -tangent.dot(&v11);
+  let side_offset2 = tangent.dot(&v12);
+
+  // clip the incident edge against the side planes of the reference edge
+  let clipped = clip_segment_to_line(&incident, -tangent, side_offset1, i11)?;
+  let clipped = clip_segment_to_line(&clipped, tangent, side_offset2, i12)?;
+
+  // keep points below the reference face
+  let mut manifold = Manifold {
+    normal: if flip { -normal } else { normal },
+    points: [ContactPoint { point: Vector2::zeros(), separation: 0.0, id: clipped[0].id }; 2],
+    count: 0
+  };
+  for clip in clipped.iter() {
+    let separation = normal.dot(&clip.v) - front_offset;
+    if separation > 0.0 { continue; }
+
+    // halfway between the incident point and its projection on the face
+    let point = clip.v - 0.5 * separation * normal;
+    let id = if flip { clip.id.flipped() } else { clip.id };
+    manifold.points[manifold.count] = ContactPoint { point, separation, id };
+    manifold.count += 1;
+  }
+
+  if manifold.count == 0 { return None; }
+  Some(manifold)
+}
+
+fn world_points(poly: &ConvexPoly, transform: &Isometry2<f32>) -> Vec<Vector2<f32>> {
+  (0..poly.num_points())
+    .map(|i| transform.transform_point(&Point2::from(poly.point(i))).coords)
+    .collect()
+}
+
+/// outward normal of a counter-clockwise edge with the given direction
+fn edge_normal(direction: Vector2<f32>) -> Vector2<f32> {
+  Vector2::new(direction.y, -direction.x)
+}
+
+/// outward unit normal of the `i`th edge of a counter-clockwise polygon
+fn outward_normal(points: &[Vector2<f32>], i: usize) -> Vector2<f32> {
+  let j = (i + 1) % points.len();
+  edge_normal((points[j] - points[i]).normalize())
+}
+
+/// Finds the edge of `poly1` whose normal separates the polygons the most,
+/// returning its index and the separation, which is negative on overlap.
+fn find_max_separation(poly1: &[Vector2<f32>], poly2: &[Vector2<f32>]) -> (usize, f32) {
+  let mut best_index = 0;
+  let mut max_separation = f32::NEG_INFINITY;
+  for i in 0..poly1.len() {
+    let normal = outward_normal(poly1, i);
+
+    // the deepest point of poly2 beyond this edge
+    let separation = poly2.iter()
+      .map(|p| normal.dot(&(p - poly1[i])))
+      .fold(f32::INFINITY, f32::min);
+
+    if separation > max_separation {
+      max_separation = separation;
+      best_index = i;
+    }
+  }
+  (best_index, max_separation)
+}
+
+/// Finds the edge of `poly2` most anti-parallel to the reference edge `edge1`
+/// of `poly1`, returning its endpoints.
+fn find_incident_edge(poly1: &[Vector2<f32>], edge1: usize, poly2: &[Vector2<f32>]) -> [ClipVertex; 2] {
+  let normal1 = outward_normal(poly1, edge1);
+
+  let index = (0..poly2.len())
+    .min_by(|&i, &j| {
+      let (di, dj) = (normal1.dot(&outward_normal(poly2, i)), normal1.dot(&outward_normal(poly2, j)));
+      di.total_cmp(&dj)
+    })
+    .unwrap();
+  let next = (index + 1) % poly2.len();
+
+  let feature = |vertex| ContactFeature {
+    index_a: edge1,
+    index_b: vertex,
+    type_a: FeatureType::Face,
+    type_b: FeatureType::Vertex
+  };
+  [
+    ClipVertex { v: poly2[index], id: feature(index) },
+    ClipVertex { v: poly2[next], id: feature(next) }
+  ]
+}
+
+/// Clips a segment to the half plane `normal · x <= offset`.  A point created
+/// by clipping is identified by `vertex_index`, the reference edge endpoint
+/// whose side plane cut it.  Returns `None` if less than two points remain.
+fn clip_segment_to_line(
+  input: &[ClipVertex; 2],
+  normal: Vector2<f32>,
+  offset: f32,
+  vertex_index: usize
+) -> Option<[ClipVertex; 2]> {
+  let distance0 = normal.dot(&input[0].v) - offset;
+  let distance1 = normal.dot(&input[1].v) - offset;
+
+  let mut output = Vec::with_capacity(2);
+  if distance0 <= 0.0 { output.push(input[0]); }
+  if distance1 <= 0.0 { output.push(input[1]); }
+
+  // the points are on opposite sides of the plane
+  if distance0 * distance1 < 0.0 {
+    let interp = distance0 / (distance0 - distance1);
+    let v = input[0].v + interp * (input[1].v - input[0].v);
+    let id = ContactFeature {
+      index_a: vertex_index,
+      index_b: input[0].id.index_b,
+      type_a: FeatureType::Vertex,
+      type_b: FeatureType::Face
+    };
+    output.push(ClipVertex { v, id });
+  }
+
+  match output[..] {
+    [first, second] => Some([first, second]),
+    _ => None
+  }
+}
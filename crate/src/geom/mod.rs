@@ -1,2 +1,9 @@
 pub mod convex_poly;
-pub use convex_poly::*;
\ No newline at end of file
+pub use convex_poly::*;
+pub mod support_map;
+pub use support_map::*;
+pub mod gjk;
+pub mod epa;
+pub mod manifold;
+pub mod shape;
+pub use shape::*;
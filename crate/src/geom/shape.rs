@@ -0,0 +1,303 @@
+extern crate nalgebra as nalg;
+
+use std::f32::consts::PI;
+
+use nalg::{Isometry2, Point2, Vector2};
+
+use crate::bvh::aabb::AABB;
+use crate::geom::manifold::{self, ContactFeature, ContactPoint, FeatureType, Manifold};
+use crate::geom::{epa, gjk, ConvexPoly, MassProperties, SupportMap};
+
+/// The geometry of a collider, in its own local frame.  Rounded shapes are
+/// described by a core point or segment inflated by a radius, so that they
+/// collide exactly rather than as many-sided polygons.
+#[derive(Debug, Clone)]
+pub enum Shape {
+  /// a disc centered on the origin
+  Circle { radius: f32 },
+  /// a segment between `a` and `b` inflated by `radius`, i.e. a stadium
+  Capsule { a: Vector2<f32>, b: Vector2<f32>, radius: f32 },
+  /// a line segment with no thickness, e.g. for static terrain
+  Segment { a: Vector2<f32>, b: Vector2<f32> },
+  /// a rectangle centered on the origin
+  Box { half_extents: Vector2<f32> },
+  Polygon(ConvexPoly),
+  /// several shapes rigidly attached, each placed by its own transform
+  Compound(Vec<(Isometry2<f32>, Shape)>)
+}
+
+/// Contact between the convex parts of two shapes.  The children are indices
+/// into a compound's list of shapes, and are zero for other shapes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeContact {
+  pub child_a: usize,
+  pub child_b: usize,
+  pub manifold: Manifold
+}
+
+/// The part of a convex shape which remains when its radius is removed.
+enum Core<'a> {
+  Point,
+  Segment(Vector2<f32>, Vector2<f32>),
+  /// a box with positive half extents
+  Box(Vector2<f32>),
+  Polygon(&'a ConvexPoly)
+}
+
+impl SupportMap for Core<'_> {
+  fn local_support_point(&self, dir: &Vector2<f32>) -> Vector2<f32> {
+    match self {
+      Core::Point => Vector2::zeros(),
+      Core::Segment(a, b) => if b.dot(dir) > a.dot(dir) { *b } else { *a },
+      Core::Box(half_extents) => Vector2::new(
+        half_extents.x.copysign(dir.x),
+        half_extents.y.copysign(dir.y)
+      ),
+      Core::Polygon(poly) => poly.local_support_point(dir)
+    }
+  }
+}
+
+impl Core<'_> {
+  /// the points whose convex hull is the core, counter-clockwise for polygons
+  fn points(&self) -> Vec<Vector2<f32>> {
+    match self {
+      Core::Point => vec![Vector2::zeros()],
+      Core::Segment(a, b) => vec![*a, *b],
+      Core::Box(half_extents) => box_corners(*half_extents).to_vec(),
+      Core::Polygon(poly) => (0..poly.num_points()).map(|i| poly.point(i)).collect()
+    }
+  }
+
+  fn world_points(&self, transform: &Isometry2<f32>) -> Vec<Vector2<f32>> {
+    self.points().into_iter().map(|p| transform_point(transform, p)).collect()
+  }
+}
+
+/// corners of a box centered on the origin, counter-clockwise
+fn box_corners(half_extents: Vector2<f32>) -> [Vector2<f32>; 4] {
+  let (x, y) = (half_extents.x, half_extents.y);
+  [Vector2::new(-x, -y), Vector2::new(x, -y), Vector2::new(x, y), Vector2::new(-x, y)]
+}
+
+fn transform_point(transform: &Isometry2<f32>, point: Vector2<f32>) -> Vector2<f32> {
+  transform.transform_point(&Point2::from(point)).coords
+}
+
+impl Shape {
+  /// A box with the given half width and half height.  A box with a zero
+  /// extent collides as the segment or point it collapses to.
+  pub fn cuboid(half_width: f32, half_height: f32) -> Shape {
+    Shape::Box { half_extents: Vector2::new(half_width, half_height) }
+  }
+
+  /// the core and radius of a convex shape, or `None` for a compound
+  fn core(&self) -> Option<(Core<'_>, f32)> {
+    match self {
+      Shape::Circle { radius } => Some((Core::Point, *radius)),
+      Shape::Capsule { a, b, radius } => Some((Core::Segment(*a, *b), *radius)),
+      Shape::Segment { a, b } => Some((Core::Segment(*a, *b), 0.0)),
+      Shape::Box { half_extents } => {
+        let (x, y) = (half_extents.x.abs(), half_extents.y.abs());
+        let core = match (x > 0.0, y > 0.0) {
+          (true, true)   => Core::Box(Vector2::new(x, y)),
+          (true, false)  => Core::Segment(Vector2::new(-x, 0.0), Vector2::new(x, 0.0)),
+          (false, true)  => Core::Segment(Vector2::new(0.0, -y), Vector2::new(0.0, y)),
+          (false, false) => Core::Point
+        };
+        Some((core, 0.0))
+      }
+      Shape::Polygon(poly) => Some((Core::Polygon(poly), 0.0)),
+      Shape::Compound(_) => None
+    }
+  }
+
+  /// Returns the bounding box of the shape placed in the world by `transform`.
+  /// An empty compound is bounded by the single point at its origin.
+  pub fn aabb(&self, transform: &Isometry2<f32>) -> AABB {
+    if let Shape::Compound(children) = self {
+      return children.iter()
+        .map(|(child_transform, child)| child.aabb(&(transform * child_transform)))
+        .reduce(|a, b| AABB::join(&a, &b))
+        .unwrap_or_else(|| {
+          let origin = transform.translation.vector;
+          AABB::new(origin, origin)
+        });
+    }
+
+    let (core, radius) = self.core().unwrap();
+    AABB::from_points(core.world_points(transform)).unwrap().expand_by_margin(radius)
+  }
+
+  /// Computes the mass, center of mass and inertia for the given density,
+  /// in the shape's local frame.  Segments have no area and so no mass.
+  pub fn mass_properties(&self, density: f32) -> MassProperties {
+    match self {
+      Shape::Circle { radius } => {
+        let mass = density * PI * radius * radius;
+        MassProperties { mass, center: Vector2::zeros(), inertia: 0.5 * mass * radius * radius }
+      }
+      Shape::Capsule { a, b, radius } => {
+        // a rectangle between two half discs, which together form a disc
+        let length = (b - a).norm();
+        let circle_mass = density * PI * radius * radius;
+        let box_mass = density * 2.0 * radius * length;
+
+        // each half disc's centroid lies `lc` beyond the end of the rectangle,
+        // and its inertia follows from the parallel axis theorem
+        let lc = 4.0 * radius / (3.0 * PI);
+        let h = 0.5 * length;
+        let circle_inertia = circle_mass * (0.5 * radius * radius + h * h + 2.0 * h * lc);
+        let box_inertia = box_mass * (4.0 * radius * radius + length * length) / 12.0;
+
+        MassProperties {
+          mass: circle_mass + box_mass,
+          center: 0.5 * (a + b),
+          inertia: circle_inertia + box_inertia
+        }
+      }
+      Shape::Segment { a, b } => {
+        MassProperties { mass: 0.0, center: 0.5 * (a + b), inertia: 0.0 }
+      }
+      Shape::Box { half_extents } => {
+        let (x, y) = (half_extents.x.abs(), half_extents.y.abs());
+        let mass = density * 4.0 * x * y;
+        MassProperties { mass, center: Vector2::zeros(), inertia: mass * (x * x + y * y) / 3.0 }
+      }
+      Shape::Polygon(poly) => poly.mass_properties(density),
+      Shape::Compound(children) => {
+        let parts: Vec<(MassProperties, Vector2<f32>)> = children.iter()
+          .map(|(transform, child)| {
+            let part = child.mass_properties(density);
+            (part, transform_point(transform, part.center))
+          })
+          .collect();
+
+        let mass: f32 = parts.iter().map(|(part, _)| part.mass).sum();
+        let center = if mass > 0.0 {
+          parts.iter().map(|(part, center)| part.mass * center).sum::<Vector2<f32>>() / mass
+        } else {
+          Vector2::zeros()
+        };
+
+        // move each part's inertia to the combined center of mass
+        let inertia = parts.iter()
+          .map(|(part, part_center)| part.inertia + part.mass * (part_center - center).norm_squared())
+          .sum();
+
+        MassProperties { mass, center, inertia }
+      }
+    }
+  }
+
+  /// Returns closed outlines of the shape in its local frame, wound
+  /// counter-clockwise, for rendering.  Curves are approximated using
+  /// `segments` points per full circle.  A segment is a two point outline,
+  /// and a compound has one outline per convex part.
+  pub fn outlines(&self, segments: usize) -> Vec<Vec<Vector2<f32>>> {
+    let arc = |center: Vector2<f32>, radius: f32, start: f32, steps: usize| {
+      (0..=steps).map(move |i| {
+        let theta = start + PI * i as f32 / steps as f32;
+        center + radius * Vector2::new(theta.cos(), theta.sin())
+      })
+    };
+
+    match self {
+      Shape::Circle { radius } => {
+        let segments = segments.max(3);
+        vec![(0..segments)
+          .map(|i| {
+            let theta = 2.0 * PI * i as f32 / segments as f32;
+            *radius * Vector2::new(theta.cos(), theta.sin())
+          })
+          .collect()]
+      }
+      Shape::Capsule { a, b, radius } => {
+        // a half circle around each end, joined by the straight sides
+        let axis = b - a;
+        let angle = if axis.norm_squared() > 0.0 { axis.y.atan2(axis.x) } else { 0.0 };
+        let steps = (segments / 2).max(1);
+        vec![arc(*b, *radius, angle - 0.5 * PI, steps)
+          .chain(arc(*a, *radius, angle + 0.5 * PI, steps))
+          .collect()]
+      }
+      Shape::Segment { a, b } => vec![vec![*a, *b]],
+      Shape::Box { half_extents } => vec![box_corners(half_extents.abs()).to_vec()],
+      Shape::Polygon(poly) => vec![(0..poly.num_points()).map(|i| poly.point(i)).collect()],
+      Shape::Compound(children) => children.iter()
+        .flat_map(|(transform, child)| {
+          child.outlines(segments).into_iter()
+            .map(move |outline| outline.into_iter().map(|p| transform_point(transform, p)).collect())
+        })
+        .collect()
+    }
+  }
+
+  /// Invokes the callback with every convex part of the shape, along with its
+  /// world transform and the index of the compound child it belongs to.
+  fn for_each_convex_part(&self, transform: &Isometry2<f32>, callback: &mut dyn FnMut(usize, &Shape, &Isometry2<f32>)) {
+    match self {
+      Shape::Compound(children) => {
+        for (index, (child_transform, child)) in children.iter().enumerate() {
+          child.for_each_convex_part(&(transform * child_transform), &mut |_, part, part_transform| {
+            callback(index, part, part_transform)
+          });
+        }
+      }
+      _ => callback(0, self, transform)
+    }
+  }
+}
+
+/// Computes the contacts between shape `a` placed by `transform_a` and shape
+/// `b` placed by `transform_b`, with one manifold per touching pair of
+/// convex parts.  Normals point from `a` towards `b`.
+pub fn collide(a: &Shape, transform_a: &Isometry2<f32>, b: &Shape, transform_b: &Isometry2<f32>) -> Vec<ShapeContact> {
+  let mut contacts = Vec::new();
+  a.for_each_convex_part(transform_a, &mut |child_a, part_a, part_transform_a| {
+    b.for_each_convex_part(transform_b, &mut |child_b, part_b, part_transform_b| {
+      if let Some(manifold) = collide_convex(part_a, part_transform_a, part_b, part_transform_b) {
+        contacts.push(ShapeContact { child_a, child_b, manifold });
+      }
+    });
+  });
+  contacts
+}
+
+/// Collides two convex shapes.  Polygons and boxes are clipped against each
+/// other to find up to two contacts; any pair involving a rounded shape or a
+/// segment is resolved with a single contact between their cores, using GJK
+/// when the cores are apart and EPA when they overlap.
+fn collide_convex(a: &Shape, transform_a: &Isometry2<f32>, b: &Shape, transform_b: &Isometry2<f32>) -> Option<Manifold> {
+  let (core_a, radius_a) = a.core()?;
+  let (core_b, radius_b) = b.core()?;
+
+  if let (Core::Box(_) | Core::Polygon(_), Core::Box(_) | Core::Polygon(_)) = (&core_a, &core_b) {
+    return manifold::collide_points(&core_a.world_points(transform_a), &core_b.world_points(transform_b));
+  }
+
+  let radius = radius_a + radius_b;
+  let result = gjk::distance(&core_a, transform_a, &core_b, transform_b);
+  if result.distance > radius { return None; }
+
+  let (normal, point_a, point_b, separation) = if !result.intersecting {
+    let normal = (result.point_b - result.point_a) / result.distance;
+    (normal, result.point_a, result.point_b, result.distance - radius)
+  } else if let Some(penetration) = epa::penetration(&core_a, transform_a, &core_b, transform_b, &result.simplex) {
+    (penetration.normal, penetration.point_a, penetration.point_b, -penetration.depth - radius)
+  } else {
+    // the cores have no area between them, e.g. crossing segments or
+    // concentric circles, so push apart along the line between origins
+    let offset = transform_b.translation.vector - transform_a.translation.vector;
+    let normal = if offset.norm_squared() > 0.0 { offset.normalize() } else { Vector2::x() };
+    (normal, result.point_a, result.point_b, -radius)
+  };
+
+  // halfway between the two surfaces
+  let surface_a = point_a + radius_a * normal;
+  let surface_b = point_b - radius_b * normal;
+  let id = ContactFeature { index_a: 0, index_b: 0, type_a: FeatureType::Vertex, type_b: FeatureType::Vertex };
+  let contact = ContactPoint { point: 0.5 * (surface_a + surface_b), separation, id };
+
+  Some(Manifold::new(normal, &[contact]))
+}
@@ -0,0 +1,39 @@
+extern crate nalgebra as nalg;
+
+use nalg::{Isometry2, Point2, Vector2};
+
+use crate::geom::ConvexPoly;
+
+/// A convex shape described by its support function, which maps a direction
+/// to the point of the shape furthest along that direction.  This is all that
+/// GJK and EPA need to know about a shape.
+pub trait SupportMap {
+  /// Returns a point of the shape, in local coordinates, which maximizes the
+  /// dot product with `dir`.  The direction need not be normalized.
+  fn local_support_point(&self, dir: &Vector2<f32>) -> Vector2<f32>;
+
+  /// Returns a support point, in world coordinates, of the shape placed in the
+  /// world by `transform`, for a direction given in world coordinates.
+  fn support_point(&self, transform: &Isometry2<f32>, dir: &Vector2<f32>) -> Vector2<f32> {
+    let local_dir = transform.inverse_transform_vector(dir);
+    let local_point = self.local_support_point(&local_dir);
+    transform.transform_point(&Point2::from(local_point)).coords
+  }
+}
+
+impl SupportMap for ConvexPoly {
+  fn local_support_point(&self, dir: &Vector2<f32>) -> Vector2<f32> {
+    // linear scan, which is fastest for the small polygons we use
+    let mut best = self.point(0);
+    let mut best_dot = best.dot(dir);
+    for i in 1..self.num_points() {
+      let point = self.point(i);
+      let dot = point.dot(dir);
+      if dot > best_dot {
+        best = point;
+        best_dot = dot;
+      }
+    }
+    best
+  }
+}
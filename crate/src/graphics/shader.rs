@@ -25,7 +25,8 @@ pub struct Shader {
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum GlslType {
   Float,
-  Vec3
+  Vec3,
+  Vec4
 }
 
 /* --- static functions ----------------------------------------------------- */
@@ -213,6 +214,18 @@ impl Shader {
     }
   }
 
+  pub fn set_uniform_vec4(&mut self, gl: &glow::Context, name: &str, x: f32, y: f32, z: f32, w: f32) {
+    let loc = self.uniform_locs.get(&(name.to_string(), GlslType::Vec4));
+
+    if loc.is_none() {
+      eprintln!("cannot find uniform vec4 {name}");
+    }
+
+    unsafe {
+      gl.uniform_4_f32(loc, x, y, z, w);
+    }
+  }
+
 }
 
 /* -------------------------------------------------------------------------- */
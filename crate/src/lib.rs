@@ -1,12 +1,13 @@
 mod utils;
 mod webgl;
-mod game_bevy;
-mod geom;
+pub mod game_bevy;
+pub mod geom;
 mod graphics;
 mod canvas;
 mod console;
 mod controls;
-mod bvh;
+pub mod bvh;
+pub mod broadphase;
 
 use console::*;
 use game_bevy::*;
@@ -0,0 +1,163 @@
+//! Native test suite for the `AABB` volume used by the BVH.
+
+use nalgebra::{Vector2, Vector3};
+use wasm_physics::bvh::aabb::{AABB, AABB3};
+
+fn aabb(x0: f32, y0: f32, x1: f32, y1: f32) -> AABB {
+  AABB::new(Vector2::new(x0, y0), Vector2::new(x1, y1))
+}
+
+#[test]
+fn join_takes_min_of_lower_and_max_of_upper() {
+  let a = aabb(0.0, 0.0, 1.0, 1.0);
+  let b = aabb(-1.0, 0.5, 0.5, 3.0);
+  assert_eq!(AABB::join(&a, &b), aabb(-1.0, 0.0, 1.0, 3.0));
+  assert_eq!(AABB::join(&b, &a), aabb(-1.0, 0.0, 1.0, 3.0));
+}
+
+#[test]
+fn join_contains_both_inputs() {
+  let a = aabb(0.0, 0.0, 1.0, 1.0);
+  let b = aabb(2.0, -2.0, 3.0, -1.0);
+  let joined = AABB::join(&a, &b);
+  assert!(joined.contains(&a));
+  assert!(joined.contains(&b));
+  assert!(joined.is_valid());
+}
+
+#[test]
+fn intersection_of_overlapping_volumes() {
+  let a = aabb(0.0, 0.0, 2.0, 2.0);
+  let b = aabb(1.0, -1.0, 3.0, 1.0);
+  assert_eq!(AABB::intersection(&a, &b), Some(aabb(1.0, 0.0, 2.0, 1.0)));
+}
+
+#[test]
+fn intersection_of_disjoint_volumes() {
+  let a = aabb(0.0, 0.0, 1.0, 1.0);
+  let b = aabb(2.0, 2.0, 3.0, 3.0);
+  assert_eq!(AABB::intersection(&a, &b), None);
+}
+
+#[test]
+fn intersection_of_touching_volumes_is_degenerate() {
+  let a = aabb(0.0, 0.0, 1.0, 1.0);
+  let b = aabb(1.0, 0.0, 2.0, 1.0);
+  let result = AABB::intersection(&a, &b).unwrap();
+  assert_eq!(result.area(), 0.0);
+}
+
+#[test]
+fn perimeter_and_area_are_distinct() {
+  let a = aabb(0.0, 0.0, 2.0, 3.0);
+  assert_eq!(a.perimeter(), 10.0);
+  assert_eq!(a.area(), 6.0);
+}
+
+#[test]
+fn expand_by_margin_grows_every_side() {
+  let a = aabb(0.0, 0.0, 1.0, 1.0).expand_by_margin(0.5);
+  assert_eq!(a, aabb(-0.5, -0.5, 1.5, 1.5));
+}
+
+#[test]
+fn translate_preserves_extents() {
+  let a = aabb(0.0, 0.0, 1.0, 2.0);
+  let b = a.translate(Vector2::new(3.0, -1.0));
+  assert_eq!(b, aabb(3.0, -1.0, 4.0, 1.0));
+  assert_eq!(a.extents(), b.extents());
+}
+
+#[test]
+fn from_points_bounds_every_point() {
+  let points = [
+    Vector2::new(1.0, 2.0),
+    Vector2::new(-1.0, 0.5),
+    Vector2::new(0.0, -3.0),
+  ];
+  assert_eq!(AABB::from_points(points), Some(aabb(-1.0, -3.0, 1.0, 2.0)));
+  assert_eq!(AABB::from_points(std::iter::empty()), None);
+}
+
+#[test]
+fn is_valid_rejects_inverted_and_non_finite_bounds() {
+  assert!(aabb(0.0, 0.0, 0.0, 0.0).is_valid());
+  assert!(!aabb(1.0, 0.0, 0.0, 1.0).is_valid());
+  assert!(!aabb(0.0, 0.0, f32::NAN, 1.0).is_valid());
+  assert!(!aabb(0.0, f32::NEG_INFINITY, 1.0, 1.0).is_valid());
+}
+
+#[test]
+fn overlaps_and_contains() {
+  let a = aabb(0.0, 0.0, 2.0, 2.0);
+  let b = aabb(1.0, 1.0, 3.0, 3.0);
+  let c = aabb(0.5, 0.5, 1.5, 1.5);
+  assert!(a.overlaps(&b));
+  assert!(b.overlaps(&a));
+  assert!(!a.contains(&b));
+  assert!(a.contains(&c));
+  assert!(a.contains_point(Vector2::new(2.0, 0.0)));
+  assert!(!a.contains_point(Vector2::new(2.1, 0.0)));
+}
+
+#[test]
+fn center_and_extents() {
+  let a = aabb(-1.0, 0.0, 3.0, 2.0);
+  assert_eq!(a.center(), Vector2::new(1.0, 1.0));
+  assert_eq!(a.extents(), Vector2::new(2.0, 1.0));
+}
+
+#[test]
+fn ray_cast_returns_entry_fraction() {
+  let a = aabb(1.0, -1.0, 2.0, 1.0);
+  let t = a.ray_cast(Vector2::new(0.0, 0.0), Vector2::new(4.0, 0.0));
+  assert_eq!(t, Some(0.25));
+}
+
+#[test]
+fn ray_cast_from_inside_returns_zero() {
+  let a = aabb(-1.0, -1.0, 1.0, 1.0);
+  let t = a.ray_cast(Vector2::new(0.0, 0.0), Vector2::new(4.0, 4.0));
+  assert_eq!(t, Some(0.0));
+}
+
+#[test]
+fn ray_cast_misses() {
+  let a = aabb(1.0, 1.0, 2.0, 2.0);
+  // parallel to the x-slab, outside of it
+  assert_eq!(a.ray_cast(Vector2::new(0.0, 0.0), Vector2::new(4.0, 0.0)), None);
+  // segment ends before reaching the volume
+  assert_eq!(a.ray_cast(Vector2::new(0.0, 0.0), Vector2::new(0.5, 0.5)), None);
+  // diagonal passing beside the volume
+  assert_eq!(a.ray_cast(Vector2::new(0.0, 3.0), Vector2::new(3.0, 2.5)), None);
+}
+
+#[test]
+fn distance_to_point_outside_and_inside() {
+  let a = aabb(0.0, 0.0, 2.0, 1.0);
+  assert_eq!(a.distance_to_point(Vector2::new(1.0, 0.5)), 0.0);
+  assert_eq!(a.distance_to_point(Vector2::new(-3.0, 0.5)), 3.0);
+  assert_eq!(a.distance_to_point(Vector2::new(5.0, 5.0)), 5.0);
+}
+
+#[test]
+fn box_cast_returns_time_of_impact() {
+  let target = aabb(4.0, 0.0, 5.0, 1.0);
+  let moving = aabb(0.0, 0.5, 1.0, 1.5);
+  // leading edge travels from x = 1 to x = 4
+  assert_eq!(target.box_cast(&moving, Vector2::new(6.0, 0.0)), Some(0.5));
+  // already overlapping
+  assert_eq!(target.box_cast(&aabb(4.5, 0.5, 5.5, 1.5), Vector2::new(1.0, 0.0)), Some(0.0));
+  // passes above the target
+  assert_eq!(target.box_cast(&moving, Vector2::new(6.0, 2.0)), None);
+}
+
+#[test]
+fn sah_measure_and_volume_in_three_dimensions() {
+  let a = AABB3::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 2.0, 3.0));
+  assert_eq!(a.sah_measure(), 22.0);
+  assert_eq!(a.volume(), 6.0);
+  assert!(a.contains_point(Vector3::new(0.5, 1.0, 2.9)));
+  assert!(!a.contains_point(Vector3::new(0.5, 1.0, 3.1)));
+  assert_eq!(a.ray_cast(Vector3::new(0.5, 1.0, -1.0), Vector3::new(0.5, 1.0, 1.0)), Some(0.5));
+}
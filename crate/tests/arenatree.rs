@@ -0,0 +1,82 @@
+//! Native test suite for the generic `ArenaTree`.
+
+use wasm_physics::bvh::arenatree::{ArenaTree, NodeId};
+
+/// builds the tree
+///
+/// ```text
+///         a
+///       / | \
+///      b  c  d
+///     / \     \
+///    e   f     g
+/// ```
+fn sample() -> (ArenaTree<char>, Vec<NodeId>) {
+  let mut tree = ArenaTree::new();
+  let a = tree.add_node('a');
+  let b = tree.add_child(a, 'b').unwrap();
+  let c = tree.add_child(a, 'c').unwrap();
+  let d = tree.add_child(a, 'd').unwrap();
+  let e = tree.add_child(b, 'e').unwrap();
+  let f = tree.add_child(b, 'f').unwrap();
+  let g = tree.add_child(d, 'g').unwrap();
+  (tree, vec![a, b, c, d, e, f, g])
+}
+
+fn values(tree: &ArenaTree<char>, ids: impl Iterator<Item = NodeId>) -> String {
+  ids.map(|id| *tree.get(id).unwrap()).collect()
+}
+
+#[test]
+fn size_edges_and_depth() {
+  let (tree, ids) = sample();
+  assert_eq!(tree.size(), 7);
+  assert_eq!(tree.edges(), 6);
+  assert_eq!(tree.depth(ids[0]), 0);
+  assert_eq!(tree.depth(ids[3]), 1);
+  assert_eq!(tree.depth(ids[5]), 2);
+}
+
+#[test]
+fn depth_first_and_breadth_first_order() {
+  let (tree, ids) = sample();
+  assert_eq!(values(&tree, tree.dfs(ids[0])), "abefcdg");
+  assert_eq!(values(&tree, tree.bfs(ids[0])), "abcdefg");
+  assert_eq!(values(&tree, tree.dfs(ids[1])), "bef");
+}
+
+#[test]
+fn lowest_common_ancestor_and_path() {
+  let (tree, ids) = sample();
+  let [a, b, _, d, e, f, g] = ids[..] else { unreachable!() };
+
+  assert_eq!(tree.lowest_common_ancestor(e, f), Some(b));
+  assert_eq!(tree.lowest_common_ancestor(e, g), Some(a));
+  assert_eq!(tree.lowest_common_ancestor(b, e), Some(b));
+  assert_eq!(tree.lowest_common_ancestor(g, g), Some(g));
+
+  assert_eq!(values(&tree, tree.path(e, g).unwrap().into_iter()), "ebadg");
+  assert_eq!(values(&tree, tree.path(d, g).unwrap().into_iter()), "dg");
+}
+
+#[test]
+fn disjoint_trees_have_no_common_ancestor() {
+  let (mut tree, ids) = sample();
+  let other = tree.add_node('z');
+  assert_eq!(tree.lowest_common_ancestor(ids[4], other), None);
+  assert_eq!(tree.path(ids[4], other), None);
+  assert_eq!(tree.roots().count(), 2);
+}
+
+#[test]
+fn remove_subtree_detaches_from_parent() {
+  let (mut tree, ids) = sample();
+  let [a, b, _, _, e, _, _] = ids[..] else { unreachable!() };
+
+  assert_eq!(tree.remove_subtree(b), Some(vec!['b', 'e', 'f']));
+  assert_eq!(tree.size(), 4);
+  assert!(!tree.contains(e));
+  assert_eq!(values(&tree, tree.dfs(a)), "acdg");
+  assert_eq!(tree.remove_subtree(b), None);
+  assert_eq!(tree.add_child(b, 'x'), None);
+}
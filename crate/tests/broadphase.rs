@@ -0,0 +1,165 @@
+//! Native test suite for the `Broadphase` implementations.
+
+use nalgebra::Vector2;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use wasm_physics::broadphase::{Broadphase, BvhBroadphase, SpatialHash, SweepAndPrune};
+use wasm_physics::broadphase::spatial_hash::periodic_overlap;
+use wasm_physics::bvh::aabb::AABB;
+
+fn random_volumes(rng: &mut StdRng, n: usize) -> Vec<AABB> {
+  (0..n).map(|_| {
+    let lower = Vector2::new(rng.gen_range(-10.0, 10.0), rng.gen_range(-10.0, 10.0));
+    let size = Vector2::new(rng.gen_range(0.1, 1.5), rng.gen_range(0.1, 1.5));
+    AABB::new(lower, lower + size)
+  }).collect()
+}
+
+fn sorted_pairs(broadphase: &mut dyn Broadphase<usize>) -> Vec<(usize, usize)> {
+  let mut pairs = vec![];
+  broadphase.for_each_pair(&mut |a, b| pairs.push((a.min(b), a.max(b))));
+  pairs.sort();
+  pairs
+}
+
+fn brute_force_pairs(volumes: &[Option<AABB>]) -> Vec<(usize, usize)> {
+  let mut pairs = vec![];
+  for i in 0..volumes.len() {
+    for j in i + 1..volumes.len() {
+      if let (Some(a), Some(b)) = (volumes[i], volumes[j]) {
+        if a.overlaps(&b) { pairs.push((i, j)); }
+      }
+    }
+  }
+  pairs
+}
+
+#[test]
+fn implementations_agree_with_brute_force() {
+  let mut rng = StdRng::seed_from_u64(7);
+  let mut volumes: Vec<Option<AABB>> = random_volumes(&mut rng, 200).into_iter().map(Some).collect();
+
+  // without fattening, the tree reports exactly the overlapping pairs
+  let mut implementations: Vec<Box<dyn Broadphase<usize>>> = vec![
+    Box::new(BvhBroadphase::with_tree(wasm_physics::bvh::aabb::Tree::with_margin(0.0, 0.0))),
+    Box::new(SweepAndPrune::new()),
+  ];
+  for broadphase in &mut implementations {
+    for (i, volume) in volumes.iter().enumerate() { broadphase.insert(i, volume.unwrap()); }
+  }
+
+  for _ in 0..20 {
+    // move some proxies and remove a few others
+    for (i, slot) in volumes.iter_mut().enumerate() {
+      match (rng.gen_range(0, 10), *slot) {
+        (0, Some(_)) => {
+          *slot = None;
+          for broadphase in &mut implementations {
+            assert!(broadphase.remove(i));
+            assert!(!broadphase.contains(&i));
+          }
+        }
+        (1..=4, Some(volume)) => {
+          let offset = Vector2::new(rng.gen_range(-0.5, 0.5), rng.gen_range(-0.5, 0.5));
+          *slot = Some(volume.translate(offset));
+          for broadphase in &mut implementations { broadphase.update(i, slot.unwrap(), offset); }
+        }
+        _ => {}
+      }
+    }
+
+    let expected = brute_force_pairs(&volumes);
+    for broadphase in &mut implementations {
+      assert_eq!(broadphase.len(), volumes.iter().flatten().count());
+      assert_eq!(sorted_pairs(broadphase.as_mut()), expected);
+    }
+  }
+}
+
+#[test]
+fn spatial_hash_pairs_across_the_seam() {
+  let world = AABB::new(Vector2::new(-1.0, -1.0), Vector2::new(1.0, 1.0));
+  let mut grid = SpatialHash::new(world, 0.25);
+
+  // straddles the right edge, and so overlaps a box near the left edge
+  grid.insert(0, AABB::new(Vector2::new(0.9, 0.0), Vector2::new(1.1, 0.2)));
+  grid.insert(1, AABB::new(Vector2::new(-0.95, 0.1), Vector2::new(-0.85, 0.3)));
+  // lies entirely outside the first period, wrapping to the bottom-left corner
+  grid.insert(2, AABB::new(Vector2::new(1.05, 1.05), Vector2::new(1.15, 1.15)));
+  grid.insert(3, AABB::new(Vector2::new(-0.9, -0.9), Vector2::new(-0.8, -0.8)));
+  // far from everything
+  grid.insert(4, AABB::new(Vector2::new(0.0, -0.5), Vector2::new(0.1, -0.4)));
+
+  assert_eq!(sorted_pairs(&mut grid), vec![(0, 1), (2, 3)]);
+
+  grid.update(1, AABB::new(Vector2::new(-0.5, 0.1), Vector2::new(-0.4, 0.3)), Vector2::zeros());
+  assert!(grid.remove(2));
+  assert_eq!(sorted_pairs(&mut grid), vec![]);
+}
+
+#[test]
+fn spatial_hash_agrees_with_periodic_brute_force() {
+  let world = AABB::new(Vector2::new(-10.0, -10.0), Vector2::new(10.0, 10.0));
+  let period = world.upper_bound - world.lower_bound;
+  let mut rng = StdRng::seed_from_u64(11);
+  let volumes = random_volumes(&mut rng, 300);
+
+  let mut grid = SpatialHash::new(world, 1.5);
+  for (i, volume) in volumes.iter().enumerate() { grid.insert(i, *volume); }
+
+  let mut expected = vec![];
+  for i in 0..volumes.len() {
+    for j in i + 1..volumes.len() {
+      if periodic_overlap(&volumes[i], &volumes[j], period) { expected.push((i, j)); }
+    }
+  }
+
+  assert!(expected.len() > brute_force_pairs(&volumes.iter().copied().map(Some).collect::<Vec<_>>()).len());
+  assert_eq!(sorted_pairs(&mut grid), expected);
+}
+
+#[test]
+fn debug_volumes_cover_the_structure() {
+  let mut rng = StdRng::seed_from_u64(5);
+  let volumes = random_volumes(&mut rng, 50);
+
+  // every internal node of the tree, exactly one of which is the root
+  let mut bvh = BvhBroadphase::new();
+  for (i, volume) in volumes.iter().enumerate() { bvh.insert(i, *volume); }
+  let mut depths = vec![];
+  bvh.for_each_debug_volume(&mut |_, depth| depths.push(depth));
+  assert_eq!(depths.len(), volumes.len() - 1);
+  assert_eq!(depths.iter().filter(|&&d| d == 0).count(), 1);
+
+  // occupied cells of the grid, which together cover every proxy center
+  let world = AABB::new(Vector2::new(-10.0, -10.0), Vector2::new(10.0, 10.0));
+  let mut grid = SpatialHash::new(world, 2.5);
+  for (i, volume) in volumes.iter().enumerate() { grid.insert(i, *volume); }
+  let mut cells = vec![];
+  grid.for_each_debug_volume(&mut |cell, _| cells.push(*cell));
+  assert!(!cells.is_empty() && cells.len() <= 64);
+  for volume in volumes.iter().filter(|v| world.contains(v)) {
+    assert!(cells.iter().any(|cell| cell.contains_point(volume.center())));
+  }
+}
+
+#[test]
+fn bvh_broadphase_snapshot_restores_pairs_and_proxies() {
+  let mut rng = StdRng::seed_from_u64(9);
+  let volumes = random_volumes(&mut rng, 100);
+
+  let mut bvh = BvhBroadphase::new();
+  for (i, volume) in volumes.iter().enumerate() { bvh.insert(i, *volume); }
+
+  let json = serde_json::to_string(&bvh).unwrap();
+  let mut restored: BvhBroadphase<usize> = serde_json::from_str(&json).unwrap();
+  assert_eq!(restored.len(), bvh.len());
+
+  let pairs = |b: &mut BvhBroadphase<usize>| {
+    let mut pairs = vec![];
+    b.for_each_pair(&mut |a, b| pairs.push((a, b)));
+    pairs
+  };
+  assert_eq!(pairs(&mut restored), pairs(&mut bvh));
+  assert!(restored.remove(42));
+  assert!(!restored.remove(42));
+}
@@ -0,0 +1,99 @@
+//! Native tests running the broadphase system on a bevy `World`.
+
+use bevy_ecs::prelude::*;
+use nalgebra::Vector2;
+use wasm_physics::broadphase::BvhBroadphase;
+use wasm_physics::bvh::aabb::{Tree, AABB};
+use wasm_physics::game_bevy::components::{Collider, Position};
+use wasm_physics::game_bevy::resources::game_state::GameState;
+use wasm_physics::game_bevy::systems::broadphase_system::{
+  broadphase_system, broadphase_toggle_system, BroadphasePairs, BroadphaseResource
+};
+
+fn collider() -> Collider {
+  Collider { volume: AABB::new(Vector2::new(-0.1, -0.1), Vector2::new(0.1, 0.1)) }
+}
+
+fn world_and_schedule() -> (World, Schedule) {
+  let mut world = World::new();
+  // without fattening, pairs are reported exactly
+  world.insert_resource(BroadphaseResource::with(BvhBroadphase::with_tree(Tree::with_margin(0.0, 0.0))));
+  world.insert_resource(BroadphasePairs::default());
+
+  let mut schedule = Schedule::default();
+  schedule.add_systems(broadphase_system);
+  (world, schedule)
+}
+
+fn tracked(world: &World, entity: Entity) -> bool {
+  world.resource::<BroadphaseResource>().broadphase.contains(&entity)
+}
+
+fn pairs(world: &World) -> Vec<(Entity, Entity)> {
+  world.resource::<BroadphasePairs>().pairs.clone()
+}
+
+#[test]
+fn system_tracks_spawns_moves_and_despawns() {
+  let (mut world, mut schedule) = world_and_schedule();
+
+  let a = world.spawn((collider(), Position { pos: (0.0, 0.0) })).id();
+  let b = world.spawn((collider(), Position { pos: (0.15, 0.0) })).id();
+  schedule.run(&mut world);
+  assert!(tracked(&world, a) && tracked(&world, b));
+  assert_eq!(pairs(&world), vec![(a.min(b), a.max(b))]);
+
+  // moving apart separates the pair
+  world.get_mut::<Position>(b).unwrap().pos = (0.5, 0.0);
+  schedule.run(&mut world);
+  assert_eq!(pairs(&world), vec![]);
+
+  // a collider is only tracked once its entity also has a position
+  let c = world.spawn(collider()).id();
+  schedule.run(&mut world);
+  assert!(!tracked(&world, c));
+  world.entity_mut(c).insert(Position { pos: (0.55, 0.05) });
+  schedule.run(&mut world);
+  assert!(tracked(&world, c));
+  assert_eq!(pairs(&world), vec![(b.min(c), b.max(c))]);
+
+  world.despawn(b);
+  schedule.run(&mut world);
+  assert!(!tracked(&world, b));
+  assert_eq!(world.resource::<BroadphaseResource>().broadphase.len(), 2);
+  assert_eq!(pairs(&world), vec![]);
+}
+
+#[test]
+fn toggling_the_spatial_hash_pairs_across_the_seam() {
+  let mut world = World::new();
+  world.insert_resource(GameState {
+    key_left: false,
+    key_right: false,
+    key_up: false,
+    key_down: false,
+    show_broadphase: false,
+    use_spatial_hash: false
+  });
+  world.insert_resource(BroadphaseResource::new());
+  world.insert_resource(BroadphasePairs::default());
+
+  let mut schedule = Schedule::default();
+  schedule.add_systems((broadphase_toggle_system.before(broadphase_system), broadphase_system));
+
+  // on opposite edges of the world, which wraps around
+  let a = world.spawn((collider(), Position { pos: ( 0.95, 0.0) })).id();
+  let b = world.spawn((collider(), Position { pos: (-0.95, 0.0) })).id();
+  schedule.run(&mut world);
+  assert_eq!(pairs(&world), vec![]);
+
+  world.resource_mut::<GameState>().use_spatial_hash = true;
+  schedule.run(&mut world);
+  assert!(tracked(&world, a) && tracked(&world, b));
+  assert_eq!(pairs(&world), vec![(a.min(b), a.max(b))]);
+
+  world.resource_mut::<GameState>().use_spatial_hash = false;
+  schedule.run(&mut world);
+  assert!(tracked(&world, a) && tracked(&world, b));
+  assert_eq!(pairs(&world), vec![]);
+}
@@ -0,0 +1,460 @@
+//! Native test suite for the dynamic AABB tree.
+
+use nalgebra::{Vector2, Vector3};
+use wasm_physics::bvh::aabb::{AABBN, Tree, TreeN, AABB, DEFAULT_MARGIN};
+
+fn unit_box(x: f32, y: f32) -> AABB {
+  AABB::new(Vector2::new(x, y), Vector2::new(x + 1.0, y + 1.0))
+}
+
+fn collect_aabb(tree: &Tree<usize>, volume: &AABB) -> Vec<usize> {
+  let mut result = vec![];
+  tree.query_aabb(volume, |_, &data| { result.push(data); true });
+  result.sort();
+  result
+}
+
+#[test]
+fn insert_and_remove_leaves() {
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  let handles: Vec<_> = (0..8)
+    .map(|i| tree.insert_leaf(unit_box(2.0 * i as f32, 0.0), i))
+    .collect();
+  tree.validate().unwrap();
+
+  assert_eq!(tree.remove_leaf(handles[3]), Some(3));
+  assert_eq!(tree.remove_leaf(handles[3]), None);
+  tree.validate().unwrap();
+  assert_eq!(collect_aabb(&tree, &AABB::new(Vector2::new(-1.0, -1.0), Vector2::new(20.0, 2.0))), vec![0, 1, 2, 4, 5, 6, 7]);
+
+  for (i, handle) in handles.into_iter().enumerate() {
+    if i != 3 { assert_eq!(tree.remove_leaf(handle), Some(i)); }
+    tree.validate().unwrap();
+  }
+  assert_eq!(tree.height(), 0);
+  assert_eq!(collect_aabb(&tree, &unit_box(0.0, 0.0)), Vec::<usize>::new());
+}
+
+#[test]
+fn move_leaf_only_reinserts_when_escaping_fat_volume() {
+  let mut tree = Tree::with_margin(0.5, 2.0);
+  let handle = tree.insert_leaf(unit_box(0.0, 0.0), 0);
+
+  // small motion stays inside the fattened volume
+  assert!(!tree.move_leaf(handle, unit_box(0.25, 0.0), Vector2::zeros()));
+  // large motion escapes and is predicted in the direction of travel
+  assert!(tree.move_leaf(handle, unit_box(2.0, 0.0), Vector2::new(1.0, 0.0)));
+  tree.validate().unwrap();
+
+  let fat = tree.fat_volume(handle).unwrap();
+  assert_eq!(*fat, AABB::new(Vector2::new(1.5, -0.5), Vector2::new(5.5, 1.5)));
+}
+
+#[test]
+fn query_point_finds_containing_leaves() {
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  for i in 0..10 { tree.insert_leaf(unit_box(i as f32 * 0.5, 0.0), i); }
+
+  let mut result = vec![];
+  tree.query_point(Vector2::new(1.25, 0.5), |_, &data| { result.push(data); true });
+  result.sort();
+  assert_eq!(result, vec![1, 2]);
+}
+
+#[test]
+fn query_aabb_terminates_early() {
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  for i in 0..10 { tree.insert_leaf(unit_box(0.0, 0.0), i); }
+
+  let mut count = 0;
+  tree.query_aabb(&unit_box(0.0, 0.0), |_, _| { count += 1; count < 3 });
+  assert_eq!(count, 3);
+}
+
+#[test]
+fn ray_cast_clips_to_closest_hit() {
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  for i in 0..10 { tree.insert_leaf(unit_box(2.0 * i as f32 + 1.0, 0.0), i); }
+
+  let mut closest = None;
+  tree.ray_cast(Vector2::new(0.0, 0.5), Vector2::new(40.0, 0.5), 1.0, |_, &data, fraction| {
+    closest = Some(data);
+    fraction
+  });
+  assert_eq!(closest, Some(0));
+}
+
+#[test]
+fn overlapping_pairs_are_reported_once() {
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  for i in 0..6 { tree.insert_leaf(unit_box(0.75 * i as f32, 0.0), i); }
+
+  let mut pairs = vec![];
+  tree.for_each_overlapping_pair(|_, &a, _, &b| pairs.push((a.min(b), a.max(b))));
+  pairs.sort();
+  assert_eq!(pairs, vec![(0, 1), (1, 2), (2, 3), (3, 4), (4, 5)]);
+}
+
+#[test]
+fn rotations_balance_sorted_insertions() {
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  let n = 256;
+  for i in 0..n {
+    tree.insert_leaf(unit_box(2.0 * i as f32, 0.0), i);
+    tree.validate().unwrap();
+  }
+
+  // a degenerate tree would have height n - 1
+  assert!(tree.height() <= 16, "height {}", tree.height());
+  assert!(tree.max_balance() <= 8, "balance {}", tree.max_balance());
+  assert!(tree.tree_cost() > 0.0);
+}
+
+/// deterministic pseudo-random boxes scattered over a square
+fn scattered_boxes(n: usize) -> Vec<AABB> {
+  let mut state: u32 = 12345;
+  let mut next = move || {
+    state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+    (state >> 8) as f32 / (1 << 24) as f32
+  };
+
+  (0..n).map(|_| {
+    let (x, y) = (100.0 * next(), 100.0 * next());
+    let (w, h) = (0.5 + 2.0 * next(), 0.5 + 2.0 * next());
+    AABB::new(Vector2::new(x, y), Vector2::new(x + w, y + h))
+  }).collect()
+}
+
+#[test]
+fn build_from_matches_incremental_queries() {
+  let boxes = scattered_boxes(500);
+
+  let mut incremental = Tree::new();
+  for (i, volume) in boxes.iter().enumerate() { incremental.insert_leaf(*volume, i); }
+  let (built, handles) = Tree::build_from(boxes.iter().copied().enumerate().map(|(i, v)| (v, i)));
+  assert_eq!(handles.len(), boxes.len());
+  built.validate().unwrap();
+
+  let query = AABB::new(Vector2::new(20.0, 30.0), Vector2::new(45.0, 60.0));
+  assert_eq!(collect_aabb(&built, &query), collect_aabb(&incremental, &query));
+  assert!(
+    built.tree_cost() <= incremental.tree_cost(),
+    "built {} incremental {}", built.tree_cost(), incremental.tree_cost()
+  );
+}
+
+#[test]
+fn rebuild_preserves_leaf_handles() {
+  let boxes = scattered_boxes(100);
+
+  let mut tree = Tree::new();
+  let handles: Vec<_> = boxes.iter().enumerate()
+    .map(|(i, volume)| tree.insert_leaf(*volume, i))
+    .collect();
+
+  tree.rebuild();
+  tree.validate().unwrap();
+
+  let everything = AABB::new(Vector2::new(-10.0, -10.0), Vector2::new(110.0, 110.0));
+  assert_eq!(collect_aabb(&tree, &everything), (0..100).collect::<Vec<_>>());
+  for (i, handle) in handles.into_iter().enumerate() {
+    assert_eq!(tree.remove_leaf(handle), Some(i));
+    tree.validate().unwrap();
+  }
+  assert_eq!(collect_aabb(&tree, &everything), Vec::<usize>::new());
+}
+
+#[test]
+fn build_from_with_margin_fattens_leaves_by_margin() {
+  let (mut tree, handles) = Tree::build_from_with_margin(
+    (0..10).map(|i| (unit_box(10.0 * i as f32, 0.0), i)), 0.5, 0.0
+  );
+  tree.validate().unwrap();
+  for (i, handle) in handles.iter().enumerate() {
+    let x = 10.0 * i as f32;
+    let fat = AABB::new(Vector2::new(x - 0.5, -0.5), Vector2::new(x + 1.5, 1.5));
+    assert_eq!(tree.fat_volume(*handle), Some(&fat));
+  }
+
+  // the margin survives a rebuild and applies to later moves
+  tree.rebuild();
+  assert!(!tree.move_leaf(handles[0], unit_box(0.25, 0.0), Vector2::zeros()));
+  assert!(tree.move_leaf(handles[0], unit_box(2.0, 0.0), Vector2::zeros()));
+  let fat = AABB::new(Vector2::new(1.5, -0.5), Vector2::new(3.5, 1.5));
+  assert_eq!(tree.fat_volume(handles[0]), Some(&fat));
+}
+
+#[test]
+fn empty_tree_is_valid() {
+  let mut tree = Tree::new();
+  tree.validate().unwrap();
+  let handle = tree.insert_leaf(unit_box(0.0, 0.0), 0);
+  tree.remove_leaf(handle);
+  tree.validate().unwrap();
+  tree.rebuild();
+  tree.validate().unwrap();
+}
+
+#[test]
+fn nearest_matches_brute_force() {
+  let boxes = scattered_boxes(300);
+  let (tree, _) = Tree::build_from(boxes.iter().copied().enumerate().map(|(i, v)| (v, i)));
+
+  for point in [Vector2::new(50.0, 50.0), Vector2::new(-20.0, 130.0), Vector2::new(3.0, 97.0)] {
+    let mut expected: Vec<(f32, usize)> = boxes.iter().enumerate()
+      .map(|(i, volume)| (volume.expand_by_margin(DEFAULT_MARGIN).distance_to_point(point), i))
+      .collect();
+    expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let (_, _, dist) = tree.nearest(point, f32::INFINITY).unwrap();
+    assert_eq!(dist, expected[0].0);
+
+    let k_nearest: Vec<f32> = tree.k_nearest(point, 10).into_iter().map(|(_, _, d)| d).collect();
+    let expected: Vec<f32> = expected[..10].iter().map(|(d, _)| *d).collect();
+    assert_eq!(k_nearest, expected);
+  }
+}
+
+#[test]
+fn nearest_respects_max_dist() {
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  tree.insert_leaf(unit_box(10.0, 0.0), 0);
+
+  assert!(tree.nearest(Vector2::new(0.0, 0.5), 9.5).is_none());
+  let (_, &data, dist) = tree.nearest(Vector2::new(0.0, 0.5), 10.0).unwrap();
+  assert_eq!((data, dist), (0, 10.0));
+
+  assert!(Tree::<usize>::new().nearest(Vector2::zeros(), f32::INFINITY).is_none());
+  assert!(tree.k_nearest(Vector2::zeros(), 0).is_empty());
+  assert_eq!(tree.k_nearest(Vector2::zeros(), 5).len(), 1);
+}
+
+#[test]
+fn k_nearest_by_refines_with_callback() {
+  // circles of radius 0.5 inscribed in unit boxes along a diagonal,
+  // so that the closest box corner is not the closest circle
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  let centers: Vec<Vector2<f32>> = (0..20)
+    .map(|i| Vector2::new(i as f32 * 0.9, i as f32 * 0.9 + (i % 3) as f32))
+    .collect();
+  for (i, c) in centers.iter().enumerate() {
+    tree.insert_leaf(AABB::new(c.add_scalar(-0.5), c.add_scalar(0.5)), i);
+  }
+
+  let point = Vector2::new(6.0, 3.0);
+  let circle_dist = |i: usize| ((centers[i] - point).norm() - 0.5).max(0.0);
+
+  let mut refined = 0;
+  let result = tree.k_nearest_by(point, 4, |_, &i, bound| {
+    refined += 1;
+    let dist = circle_dist(i);
+    assert!(dist >= bound);
+    dist
+  });
+
+  let mut expected: Vec<(f32, usize)> = (0..centers.len()).map(|i| (circle_dist(i), i)).collect();
+  expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+  let result: Vec<(f32, usize)> = result.into_iter().map(|(_, &i, d)| (d, i)).collect();
+  assert_eq!(result, expected[..4]);
+  // far leaves are pruned before being refined
+  assert!(refined < centers.len(), "refined {refined}");
+
+  // returning infinity excludes a leaf
+  let (_, &closest, _) = tree.nearest_by(point, f32::INFINITY, |_, &i, _| {
+    if i == expected[0].1 { f32::INFINITY } else { circle_dist(i) }
+  }).unwrap();
+  assert_eq!(closest, expected[1].1);
+}
+
+#[test]
+fn box_cast_catches_small_leaves_between_endpoints() {
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  // thin asteroids which a point-sampled bullet would step over
+  for i in 0..10 {
+    let x = 3.0 + 2.0 * i as f32;
+    tree.insert_leaf(AABB::new(Vector2::new(x, 0.0), Vector2::new(x + 0.1, 0.1)), i);
+  }
+
+  let bullet = AABB::new(Vector2::new(0.0, 0.0), Vector2::new(0.2, 0.2));
+  let displacement = Vector2::new(10.0, 0.0);
+  let mut hits = vec![];
+  tree.box_cast(&bullet, displacement, |_, &data, fraction| { hits.push((data, fraction)); -1.0 });
+  hits.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+  let data: Vec<_> = hits.iter().map(|(data, _)| *data).collect();
+  assert_eq!(data, vec![0, 1, 2, 3]);
+  assert!((hits[0].1 - 0.28).abs() < 1e-6);
+
+  // clipping finds the first impact
+  let mut first = None;
+  tree.box_cast(&bullet, displacement, |_, &data, fraction| { first = Some(data); fraction });
+  assert_eq!(first, Some(0));
+}
+
+#[test]
+fn to_dot_lists_every_node_and_edge() {
+  let (tree, _) = Tree::build_from(scattered_boxes(20).into_iter().enumerate().map(|(i, v)| (v, i)));
+  let dot = tree.to_dot();
+
+  assert!(dot.starts_with("digraph bvh {"));
+  assert!(dot.trim_end().ends_with('}'));
+  assert_eq!(dot.matches("[label=\"leaf").count(), 20);
+  assert_eq!(dot.matches("[label=\"internal").count(), 19);
+  assert_eq!(dot.matches(" -> ").count(), 38);
+  // only internal nodes contribute to the tree cost
+  assert_eq!(dot.matches("\\nsah cost ").count(), 19);
+
+  let empty = Tree::<usize>::new().to_dot();
+  assert_eq!(empty.matches(" -> ").count(), 0);
+  assert!(!empty.contains("[label="));
+}
+
+#[test]
+fn to_svg_draws_every_volume() {
+  let (tree, _) = Tree::build_from(scattered_boxes(20).into_iter().enumerate().map(|(i, v)| (v, i)));
+  let svg = tree.to_svg();
+
+  assert!(svg.starts_with("<svg "));
+  assert!(svg.trim_end().ends_with("</svg>"));
+  assert_eq!(svg.matches("<rect ").count(), 39);
+  // the root and its children are at different depths
+  assert!(svg.contains("stroke=\"hsl(0, 80%, 40%)\""));
+  assert!(svg.matches("stroke=\"hsl(").count() > svg.matches("stroke=\"hsl(0,").count());
+
+  assert_eq!(Tree::<usize>::new().to_svg().matches("<rect ").count(), 0);
+}
+
+#[test]
+fn three_dimensional_tree_matches_brute_force() {
+  // scatter boxes through a cube, reusing the 2D generator for two axes
+  let boxes: Vec<AABBN<f32, 3>> = scattered_boxes(300).iter().zip(scattered_boxes(301).iter().skip(1))
+    .map(|(a, b)| AABBN::new(
+      Vector3::new(a.lower_bound.x, a.lower_bound.y, b.lower_bound.x),
+      Vector3::new(a.upper_bound.x, a.upper_bound.y, b.upper_bound.x)
+    ))
+    .collect();
+
+  let mut tree: TreeN<usize, f32, 3> = TreeN::with_margin(0.0, 0.0);
+  for (i, volume) in boxes.iter().enumerate() { tree.insert_leaf(*volume, i); }
+  tree.validate().unwrap();
+  tree.rebuild();
+  tree.validate().unwrap();
+
+  let query = AABBN::new(Vector3::new(20.0, 20.0, 20.0), Vector3::new(60.0, 60.0, 60.0));
+  let mut result = vec![];
+  tree.query_aabb(&query, |_, &i| { result.push(i); true });
+  result.sort();
+  let expected: Vec<usize> = (0..boxes.len()).filter(|&i| boxes[i].overlaps(&query)).collect();
+  assert!(!expected.is_empty());
+  assert_eq!(result, expected);
+
+  let point = Vector3::new(50.0, 50.0, 50.0);
+  let (_, _, dist) = tree.nearest(point, f32::INFINITY).unwrap();
+  let expected = boxes.iter().map(|v| v.distance_to_point(point)).fold(f32::INFINITY, f32::min);
+  assert_eq!(dist, expected);
+}
+
+#[test]
+fn double_precision_tree_separates_distant_neighbours() {
+  // far from the origin, f32 cannot represent the gap between these boxes
+  let offset = 1.0e8;
+  let mut tree: TreeN<usize, f64, 2> = TreeN::with_margin(0.0, 0.0);
+  for i in 0..10 {
+    let x = offset + i as f64 * 1.0e-3;
+    tree.insert_leaf(AABBN::new(Vector2::new(x, 0.0), Vector2::new(x + 0.5e-3, 1.0)), i);
+  }
+  tree.validate().unwrap();
+
+  let mut result = vec![];
+  tree.query_point(Vector2::new(offset + 3.25e-3, 0.5), |_, &i| { result.push(i); true });
+  assert_eq!(result, vec![3]);
+}
+
+#[test]
+fn serialized_tree_restores_exact_structure() {
+  let boxes = scattered_boxes(200);
+  let mut tree = Tree::new();
+  let mut handles: Vec<_> = boxes.iter().enumerate()
+    .map(|(i, volume)| tree.insert_leaf(*volume, i))
+    .collect();
+
+  // churn the tree so that the arena has free slots and stale handles
+  for i in (0..200).step_by(3) {
+    tree.move_leaf(handles[i], boxes[i].translate(Vector2::new(5.0, -3.0)), Vector2::new(0.5, 0.0));
+  }
+  for i in (0..200).step_by(7) { tree.remove_leaf(handles[i]); }
+  let stale = handles[0];
+
+  let json = serde_json::to_string(&tree).unwrap();
+  let mut restored: Tree<usize> = serde_json::from_str(&json).unwrap();
+  restored.validate().unwrap();
+  assert_eq!(restored.to_dot(), tree.to_dot());
+
+  let pairs = |tree: &Tree<usize>| {
+    let mut pairs = vec![];
+    tree.for_each_overlapping_pair(|_, &a, _, &b| pairs.push((a, b)));
+    pairs
+  };
+  assert_eq!(pairs(&restored), pairs(&tree));
+
+  // handles, including stale ones, behave identically in both trees
+  assert_eq!(restored.remove_leaf(stale), None);
+  handles.retain(|&handle| tree.fat_volume(handle).is_some());
+  for &handle in &handles {
+    assert_eq!(restored.fat_volume(handle), tree.fat_volume(handle));
+  }
+
+  // trees restored from the same snapshot evolve identically afterwards
+  let mut other: Tree<usize> = serde_json::from_str(&json).unwrap();
+  let new_volume = AABB::new(Vector2::new(40.0, 40.0), Vector2::new(41.0, 42.0));
+  assert_eq!(restored.insert_leaf(new_volume, 1000), other.insert_leaf(new_volume, 1000));
+  for &handle in handles.iter().take(20) {
+    assert_eq!(restored.remove_leaf(handle), other.remove_leaf(handle));
+  }
+  assert_eq!(restored.to_dot(), other.to_dot());
+}
+
+#[test]
+fn restored_tree_evolves_like_the_live_tree() {
+  let mut live = Tree::with_margin(0.0, 0.0);
+  let handles: Vec<_> = (0..6).map(|i| live.insert_leaf(unit_box(10.0 * i as f32, 100.0), i)).collect();
+  // leave free slots, which a restored tree hands out in a different order
+  for i in [1, 4, 2, 3] { live.remove_leaf(handles[i]); }
+
+  let json = serde_json::to_string(&live).unwrap();
+  let restored: Tree<usize> = serde_json::from_str(&json).unwrap();
+
+  let mut trees = [live, restored];
+  for tree in &mut trees {
+    // the third leaf lies exactly between the first two, so pairing it
+    // with either one costs the same
+    tree.insert_leaf(unit_box(0.0, 0.0), 10);
+    tree.insert_leaf(unit_box(4.0, 0.0), 11);
+    tree.insert_leaf(unit_box(2.0, 0.0), 12);
+    // a leaf with the same center, which a rebuild splits by order alone
+    tree.insert_leaf(AABB::new(Vector2::new(1.0, -1.0), Vector2::new(4.0, 2.0)), 13);
+  }
+  assert_eq!(trees[0].to_dot(), trees[1].to_dot());
+
+  // the second nearest leaf is tied between the first and third leaves
+  let nearest = |tree: &Tree<usize>| -> Vec<usize> {
+    tree.k_nearest(Vector2::new(1.5, 5.0), 2).into_iter().map(|(_, &i, _)| i).collect()
+  };
+  assert_eq!(nearest(&trees[0]), nearest(&trees[1]));
+
+  for tree in &mut trees { tree.rebuild(); }
+  assert_eq!(trees[0].to_dot(), trees[1].to_dot());
+}
+
+#[test]
+fn sibling_search_descends_below_the_root() {
+  let mut tree = Tree::with_margin(0.0, 0.0);
+  for i in 0..4 { tree.insert_leaf(unit_box(100.0 * i as f32, 0.0), i); }
+  let before = tree.tree_cost();
+
+  // pairing with the adjacent leaf only adds the perimeter of the two joined
+  // boxes, whereas pairing with the root would add that of the whole tree
+  tree.insert_leaf(unit_box(1.0, 0.0), 4);
+  let added = tree.tree_cost() - before;
+  assert!(added <= 6.0 + 1e-3, "added {added}");
+}
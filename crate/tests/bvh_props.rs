@@ -0,0 +1,69 @@
+//! Property tests for the dynamic AABB tree: random sequences of operations
+//! are compared against a brute-force linear scan.
+
+#[path = "support/bvh_ops.rs"]
+mod bvh_ops;
+
+use bvh_ops::{Harness, Op};
+use nalgebra::Vector2;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use wasm_physics::bvh::aabb::AABB;
+
+const NUM_SEEDS: u64 = 32;
+const NUM_OPS: usize = 400;
+
+/// coordinates are quantized so that overlaps and shared edges are common
+fn random_point(rng: &mut StdRng) -> Vector2<f32> {
+  let x = rng.gen_range(-64, 64) as f32 / 4.0;
+  let y = rng.gen_range(-64, 64) as f32 / 4.0;
+  Vector2::new(x, y)
+}
+
+fn random_volume(rng: &mut StdRng) -> AABB {
+  let lower = random_point(rng);
+  let size = Vector2::new(rng.gen_range(0, 12) as f32, rng.gen_range(0, 12) as f32) / 4.0;
+  AABB::new(lower, lower + size)
+}
+
+/// operations are weighted towards insertion so the tree grows
+fn random_op(rng: &mut StdRng) -> Op {
+  match rng.gen_range(0, 22) {
+    0..=5   => Op::Insert(random_volume(rng)),
+    6..=8   => Op::Remove(rng.gen()),
+    9..=12  => Op::Move(rng.gen(), random_volume(rng), random_point(rng) / 16.0),
+    13..=14 => Op::QueryAabb(random_volume(rng)),
+    15..=16 => Op::QueryPoint(random_point(rng)),
+    17..=18 => Op::RayCast(random_point(rng), random_point(rng)),
+    19..=20 => Op::BoxCast(random_volume(rng), random_point(rng)),
+    _       => Op::Rebuild,
+  }
+}
+
+#[test]
+fn random_operations_match_brute_force() {
+  for seed in 0..NUM_SEEDS {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut harness = Harness::new();
+
+    for step in 0..NUM_OPS {
+      let op = random_op(&mut rng);
+      let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| harness.apply(&op)));
+      if result.is_err() {
+        panic!("seed {seed} failed at step {step} on {op:?}");
+      }
+    }
+  }
+}
+
+#[test]
+fn decoded_operations_match_brute_force() {
+  // exercise the fuzz target's decoder on a fixed pseudo-random input
+  let mut rng = StdRng::seed_from_u64(0);
+  let input: Vec<u8> = (0..4096).map(|_| rng.gen()).collect();
+
+  let mut bytes = &input[..];
+  let mut harness = Harness::new();
+  while let Some(op) = bvh_ops::decode_op(&mut bytes) {
+    harness.apply(&op);
+  }
+}
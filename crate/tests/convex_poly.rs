@@ -0,0 +1,133 @@
+//! Native test suite for `geom::ConvexPoly`.
+
+#[path = "support/geom.rs"]
+mod support;
+
+use nalgebra::Vector2;
+use support::{assert_near, v};
+use wasm_physics::geom::{ConvexPoly, ConvexPolyError};
+
+fn points(poly: &ConvexPoly) -> Vec<Vector2<f32>> {
+  (0..poly.num_points()).map(|i| poly.point(i)).collect()
+}
+
+#[test]
+fn from_points_discards_interior_duplicate_and_collinear_points() {
+  let poly = ConvexPoly::from_points([
+    v(1.0, 1.0), v(0.0, 0.0), v(2.0, 0.0), v(0.5, 0.5),
+    v(2.0, 2.0), v(1.0, 0.0), v(0.0, 2.0), v(2.0, 2.0), v(0.0, 1.0),
+  ]).unwrap();
+
+  assert_eq!(points(&poly), vec![v(0.0, 0.0), v(2.0, 0.0), v(2.0, 2.0), v(0.0, 2.0)]);
+}
+
+#[test]
+fn from_points_winds_counter_clockwise() {
+  let clockwise = [v(0.0, 0.0), v(0.0, 1.0), v(1.0, 1.0), v(1.0, 0.0)];
+  let poly = ConvexPoly::from_points(clockwise).unwrap();
+
+  // and so is accepted by the stricter constructor
+  assert!(ConvexPoly::try_new(points(&poly)).is_ok());
+  assert_eq!(ConvexPoly::try_new(clockwise).err(), Some(ConvexPolyError::Reflex { index: 0 }));
+}
+
+#[test]
+fn from_points_hull_of_regular_polygon_is_unchanged() {
+  let regular = ConvexPoly::regular(7, 2.0);
+  let hull = ConvexPoly::from_points(points(&regular).into_iter().rev()).unwrap();
+  assert_eq!(hull.num_points(), 7);
+  assert!(ConvexPoly::try_new(points(&regular)).is_ok());
+}
+
+#[test]
+fn from_points_rejects_degenerate_input() {
+  assert_eq!(
+    ConvexPoly::from_points([v(0.0, 0.0), v(1.0, 1.0)]).err(),
+    Some(ConvexPolyError::TooFewPoints { count: 2 })
+  );
+  assert_eq!(
+    ConvexPoly::from_points([v(0.0, 0.0), v(1.0, 1.0), v(3.0, 3.0), v(1.0, 1.0)]).err(),
+    Some(ConvexPolyError::Degenerate)
+  );
+  assert_eq!(
+    ConvexPoly::from_points([v(0.0, 0.0), v(1.0, f32::NAN), v(3.0, 3.0)]).err(),
+    Some(ConvexPolyError::NonFinite { index: 1 })
+  );
+}
+
+#[test]
+fn try_new_describes_the_first_problem() {
+  assert_eq!(
+    ConvexPoly::try_new([v(0.0, 0.0), v(1.0, 0.0), v(1.0, 0.0), v(0.0, 1.0)]).err(),
+    Some(ConvexPolyError::Collinear { index: 1 })
+  );
+  assert_eq!(
+    ConvexPoly::try_new([v(0.0, 0.0), v(1.0, 0.0), v(2.0, 0.0), v(1.0, 1.0)]).err(),
+    Some(ConvexPolyError::Collinear { index: 1 })
+  );
+  // an arrowhead with a reflex point
+  assert_eq!(
+    ConvexPoly::try_new([v(0.0, 0.0), v(2.0, 1.0), v(0.0, 2.0), v(1.0, 1.0)]).err(),
+    Some(ConvexPolyError::Reflex { index: 3 })
+  );
+
+  // a pentagram only ever turns left, but winds around twice
+  let star: Vec<Vector2<f32>> = (0..5)
+    .map(|i| {
+      let theta = (2 * i) as f32 * 2.0 * std::f32::consts::PI / 5.0;
+      v(theta.cos(), theta.sin())
+    })
+    .collect();
+  assert_eq!(ConvexPoly::try_new(star).err(), Some(ConvexPolyError::SelfIntersecting));
+
+  let err = ConvexPoly::try_new([v(0.0, 0.0)]).err().unwrap();
+  assert_eq!(err.to_string(), "a polygon needs at least 3 points, but 1 were given");
+}
+
+#[test]
+fn mass_properties_of_regular_polygons_match_closed_forms() {
+  use std::f32::consts::PI;
+
+  for n in 3..=12u16 {
+    for scale in [0.08, 1.0, 3.5] {
+      let poly = ConvexPoly::regular(n, scale);
+      let n = n as f32;
+      let density = 2.5;
+
+      // n isoceles triangles with apex angle 2π/n and legs of length R
+      let area = 0.5 * n * scale * scale * (2.0 * PI / n).sin();
+      assert_near(poly.area(), area, 1e-5);
+
+      let centroid = poly.centroid();
+      assert!(centroid.norm() <= 1e-6 * scale, "n {n} centroid {centroid}");
+
+      // I = m R² (1 + 2 cos²(π/n)) / 6 about the center
+      let mass = density * area;
+      let inertia = mass * scale * scale * (1.0 + 2.0 * (PI / n).cos().powi(2)) / 6.0;
+      assert_near(poly.moment_of_inertia(density), inertia, 1e-5);
+
+      let props = poly.mass_properties(density);
+      assert_near(props.mass, mass, 1e-5);
+      assert_near(props.inertia, inertia, 1e-5);
+    }
+  }
+}
+
+#[test]
+fn mass_properties_of_offset_right_triangle() {
+  // legs of length 3 and 6, far from the origin
+  let mut poly = ConvexPoly::try_new([v(100.0, 50.0), v(103.0, 50.0), v(100.0, 56.0)]).unwrap();
+
+  assert_near(poly.area(), 9.0, 1e-5);
+  let centroid = poly.centroid();
+  assert_near(centroid.x, 101.0, 1e-5);
+  assert_near(centroid.y, 52.0, 1e-5);
+  // I = m (a² + b²) / 18 about the centroid of a right triangle
+  assert_near(poly.moment_of_inertia(2.0), 18.0 * (9.0 + 36.0) / 18.0, 1e-5);
+
+  let old_centroid = poly.recenter();
+  assert_eq!(old_centroid, centroid);
+  assert!(poly.centroid().norm() < 1e-5);
+  assert_eq!(poly.point(0), v(-1.0, -2.0));
+  assert_near(poly.moment_of_inertia(2.0), 45.0, 1e-5);
+}
@@ -0,0 +1,95 @@
+//! Native test suite for `geom::epa`.
+
+#[path = "support/geom.rs"]
+mod support;
+
+use nalgebra::{Isometry2, Vector2};
+use support::{assert_close, at, random_placed_pairs, square, v, world_points};
+use wasm_physics::geom::{epa, gjk, ConvexPoly};
+
+fn penetration(a: &ConvexPoly, transform_a: &Isometry2<f32>, b: &ConvexPoly, transform_b: &Isometry2<f32>) -> epa::Penetration {
+  let result = gjk::distance(a, transform_a, b, transform_b);
+  assert!(result.intersecting);
+  epa::penetration(a, transform_a, b, transform_b, &result.simplex).unwrap()
+}
+
+/// The smallest overlap of the polygons' projections onto any edge normal,
+/// which for convex polygons is exactly the penetration depth.
+fn sat_depth(a: &[Vector2<f32>], b: &[Vector2<f32>]) -> f32 {
+  let project = |points: &[Vector2<f32>], axis: Vector2<f32>| {
+    points.iter().map(|p| p.dot(&axis)).fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), d| (lo.min(d), hi.max(d)))
+  };
+  a.iter().zip(a.iter().cycle().skip(1))
+    .chain(b.iter().zip(b.iter().cycle().skip(1)))
+    .map(|(p, q)| {
+      let edge = q - p;
+      let axis = v(edge.y, -edge.x).normalize();
+      let ((a_lo, a_hi), (b_lo, b_hi)) = (project(a, axis), project(b, axis));
+      (a_hi - b_lo).min(b_hi - a_lo)
+    })
+    .fold(f32::INFINITY, f32::min)
+}
+
+#[test]
+fn overlapping_squares_report_depth_normal_and_witness_points() {
+  let result = penetration(&square(), &at(0.0, 0.0, 0.0), &square(), &at(1.5, 0.25, 0.0));
+
+  assert!((result.depth - 0.5).abs() < 1e-5);
+  assert_close(result.normal, v(1.0, 0.0));
+  assert!((result.point_a.x - 1.0).abs() < 1e-5);
+  assert!((result.point_b.x - 0.5).abs() < 1e-5);
+  assert_close(result.point_a - result.point_b, result.depth * result.normal);
+}
+
+#[test]
+fn contained_shape_exits_through_nearest_side() {
+  let small = ConvexPoly::regular(4, 0.1);
+  let result = penetration(&square(), &at(0.0, 0.0, 0.0), &small, &at(0.2, 0.7, 0.0));
+
+  // the small diamond must move up past y = 1, its lowest point being at 0.6
+  assert!((result.depth - 0.4).abs() < 1e-5);
+  assert_close(result.normal, v(0.0, 1.0));
+}
+
+#[test]
+fn touching_shapes_have_zero_depth() {
+  // edge to edge, where GJK stops on a segment
+  let result = penetration(&square(), &at(0.0, 0.0, 0.0), &square(), &at(2.0, 0.5, 0.0));
+  assert!(result.depth.abs() < 1e-5);
+  assert_close(result.normal, v(1.0, 0.0));
+
+  // corner to corner, where GJK stops on a single point
+  let result = penetration(&square(), &at(0.0, 0.0, 0.0), &square(), &at(2.0, 2.0, 0.0));
+  assert!(result.depth.abs() < 1e-5);
+  assert!(result.normal.x >= -1e-5 && result.normal.y >= -1e-5);
+  assert!((result.normal.norm() - 1.0).abs() < 1e-5);
+  assert_close(result.point_a, v(1.0, 1.0));
+}
+
+#[test]
+fn same_shape_at_the_same_place() {
+  let result = penetration(&square(), &at(0.0, 0.0, 0.0), &square(), &at(0.0, 0.0, 0.0));
+  assert!((result.depth - 2.0).abs() < 1e-5);
+}
+
+#[test]
+fn agrees_with_separating_axes_on_random_polygons() {
+  let mut checked = 0;
+
+  for ((a, transform_a), (b, transform_b)) in random_placed_pairs(23, 500, 0.5) {
+    let expected = sat_depth(&world_points(&a, &transform_a), &world_points(&b, &transform_b));
+    if expected < 1e-3 { continue; }
+
+    let result = penetration(&a, &transform_a, &b, &transform_b);
+    assert!((result.depth - expected).abs() < 1e-4, "{} != {}", result.depth, expected);
+    assert_close(result.point_a - result.point_b, result.depth * result.normal);
+
+    // moving B out along the normal just separates the shapes
+    let translation = |d: f32| Isometry2::new(transform_b.translation.vector + d * result.normal, transform_b.rotation.angle());
+    assert!(!gjk::distance(&a, &transform_a, &b, &translation(result.depth + 1e-3)).intersecting);
+    assert!(gjk::distance(&a, &transform_a, &b, &translation(result.depth - 1e-3)).intersecting);
+    checked += 1;
+  }
+
+  assert!(checked > 100);
+}
@@ -0,0 +1,129 @@
+//! Native test suite for `geom::gjk`.
+
+#[path = "support/geom.rs"]
+mod support;
+
+use nalgebra::Vector2;
+use support::{assert_close, at, random_placed_pairs, square, v, world_points};
+use wasm_physics::geom::{gjk, ConvexPoly, SupportMap};
+
+/// A disc, to check that curved shapes work through the trait alone.
+struct Circle { radius: f32 }
+
+impl SupportMap for Circle {
+  fn local_support_point(&self, dir: &Vector2<f32>) -> Vector2<f32> {
+    self.radius * dir.normalize()
+  }
+}
+
+fn point_segment_distance(p: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+  let ab = b - a;
+  let t = ((p - a).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0);
+  (a + t * ab - p).norm()
+}
+
+/// whether the polygons are separated along any of their edge normals
+fn separated(a: &[Vector2<f32>], b: &[Vector2<f32>]) -> bool {
+  let separated_by_edges_of = |p: &[Vector2<f32>], q: &[Vector2<f32>]| {
+    (0..p.len()).any(|i| {
+      let edge = p[(i + 1) % p.len()] - p[i];
+      let normal = v(edge.y, -edge.x);
+      q.iter().all(|&x| normal.dot(&(x - p[i])) > 0.0)
+    })
+  };
+  separated_by_edges_of(a, b) || separated_by_edges_of(b, a)
+}
+
+/// distance between separated polygons, from every vertex to every edge
+fn brute_force_distance(a: &[Vector2<f32>], b: &[Vector2<f32>]) -> f32 {
+  let one_way = |p: &[Vector2<f32>], q: &[Vector2<f32>]| {
+    p.iter().flat_map(|&x| {
+      (0..q.len()).map(move |i| point_segment_distance(x, q[i], q[(i + 1) % q.len()]))
+    }).fold(f32::INFINITY, f32::min)
+  };
+  one_way(a, b).min(one_way(b, a))
+}
+
+#[test]
+fn separated_squares_report_distance_and_closest_points() {
+  let result = gjk::distance(&square(), &at(0.0, 0.0, 0.0), &square(), &at(3.0, 0.5, 0.0));
+
+  assert!(!result.intersecting);
+  assert!((result.distance - 1.0).abs() < 1e-5);
+  // the closest features are parallel edges, so any points on the shared
+  // span of the faces are valid, but they must be one unit apart along x
+  assert_close(result.point_b - result.point_a, v(1.0, 0.0));
+  assert!((result.point_a.x - 1.0).abs() < 1e-5);
+  assert!((-0.5..=1.0).contains(&result.point_a.y));
+}
+
+#[test]
+fn rotated_square_corner_faces_edge() {
+  let diagonal = 2.0_f32.sqrt();
+  let b = at(1.5 + diagonal, 0.0, std::f32::consts::FRAC_PI_4);
+  let result = gjk::distance(&square(), &at(0.0, 0.0, 0.0), &square(), &b);
+
+  assert!(!result.intersecting);
+  assert!((result.distance - 0.5).abs() < 1e-5);
+  assert_close(result.point_a, v(1.0, 0.0));
+  assert_close(result.point_b, v(1.5, 0.0));
+}
+
+#[test]
+fn overlapping_and_touching_shapes_intersect() {
+  let overlapping = gjk::distance(&square(), &at(0.0, 0.0, 0.0), &square(), &at(1.5, 0.3, 0.4));
+  assert!(overlapping.intersecting);
+  assert_eq!(overlapping.distance, 0.0);
+
+  let touching = gjk::distance(&square(), &at(0.0, 0.0, 0.0), &square(), &at(2.0, 0.0, 0.0));
+  assert!(touching.intersecting);
+  assert_eq!(touching.distance, 0.0);
+
+  // a shape entirely inside another
+  let small = ConvexPoly::regular(5, 0.25);
+  let inside = gjk::distance(&square(), &at(0.0, 0.0, 0.0), &small, &at(0.1, -0.2, 1.0));
+  assert!(inside.intersecting);
+}
+
+#[test]
+fn circle_against_polygon() {
+  let circle = Circle { radius: 0.5 };
+  let result = gjk::distance(&square(), &at(0.0, 0.0, 0.0), &circle, &at(3.0, 3.0, 0.0));
+
+  let expected = 8.0_f32.sqrt() - 0.5;
+  assert!(!result.intersecting);
+  assert!((result.distance - expected).abs() < 1e-3, "{}", result.distance);
+  assert_close(result.point_a, v(1.0, 1.0));
+
+  let result = gjk::distance(&circle, &at(1.2, 0.0, 0.0), &square(), &at(0.0, 0.0, 0.0));
+  assert!(result.intersecting);
+}
+
+#[test]
+fn agrees_with_brute_force_on_random_polygons() {
+  for ((a, transform_a), (b, transform_b)) in random_placed_pairs(22, 500, 2.0) {
+    let result = gjk::distance(&a, &transform_a, &b, &transform_b);
+    let (points_a, points_b) = (world_points(&a, &transform_a), world_points(&b, &transform_b));
+
+    if separated(&points_a, &points_b) {
+      let expected = brute_force_distance(&points_a, &points_b);
+      if expected < 1e-3 { continue; }
+      assert!(!result.intersecting);
+      assert!((result.distance - expected).abs() < 1e-4, "{} != {}", result.distance, expected);
+      assert!(((result.point_b - result.point_a).norm() - expected).abs() < 1e-4);
+    } else {
+      assert!(result.intersecting);
+    }
+  }
+}
+
+#[test]
+fn search_starts_from_support_points_facing_each_other() {
+  // seeding along the line between the origins picks B's corner facing A
+  // straight away, leaving only A's closest face to be found
+  let diagonal = 2.0_f32.sqrt();
+  let b = at(1.5 + diagonal, 0.0, std::f32::consts::FRAC_PI_4);
+  let result = gjk::distance(&square(), &at(0.0, 0.0, 0.0), &square(), &b);
+  assert!(result.iterations <= 4, "{} iterations", result.iterations);
+  assert_close(result.point_b, v(1.5, 0.0));
+}
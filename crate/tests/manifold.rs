@@ -0,0 +1,95 @@
+//! Native test suite for `geom::manifold`.
+
+use std::collections::HashSet;
+
+#[path = "support/geom.rs"]
+mod support;
+
+use support::{assert_close, at, random_placed_pairs, square, v};
+use wasm_physics::geom::manifold::{collide_polygons, FeatureType};
+use wasm_physics::geom::{epa, gjk};
+
+#[test]
+fn resting_box_has_two_contacts() {
+  let manifold = collide_polygons(&square(), &at(0.0, 0.0, 0.0), &square(), &at(0.5, 1.9, 0.0)).unwrap();
+  let points = manifold.points();
+
+  assert_close(manifold.normal, v(0.0, 1.0));
+  assert_eq!(points.len(), 2);
+  for contact in points {
+    assert!((contact.separation + 0.1).abs() < 1e-5);
+    assert!((contact.point.y - 0.95).abs() < 1e-5);
+  }
+
+  // one corner of B's bottom edge, and one point cut by A's right side
+  let mut xs: Vec<f32> = points.iter().map(|c| c.point.x).collect();
+  xs.sort_by(f32::total_cmp);
+  assert!((xs[0] + 0.5).abs() < 1e-5 && (xs[1] - 1.0).abs() < 1e-5);
+
+  let corner = points.iter().find(|c| c.point.x < 0.0).unwrap();
+  assert_eq!((corner.id.type_a, corner.id.type_b), (FeatureType::Face, FeatureType::Vertex));
+  let clipped = points.iter().find(|c| c.point.x > 0.0).unwrap();
+  assert_eq!((clipped.id.type_a, clipped.id.type_b), (FeatureType::Vertex, FeatureType::Face));
+}
+
+#[test]
+fn separated_polygons_have_no_manifold() {
+  assert!(collide_polygons(&square(), &at(0.0, 0.0, 0.0), &square(), &at(0.0, 2.1, 0.0)).is_none());
+  assert!(collide_polygons(&square(), &at(0.0, 0.0, 0.0), &square(), &at(2.05, 2.05, 0.3)).is_none());
+}
+
+#[test]
+fn corner_into_face_of_b_keeps_normal_from_a_to_b() {
+  // a diamond below B, poking its top corner 0.2 into B's bottom face
+  let diamond = at(0.0, 0.0, std::f32::consts::FRAC_PI_4);
+  let top = 2.0_f32.sqrt();
+  let b = at(0.1, top + 0.8, 0.0);
+  let manifold = collide_polygons(&square(), &diamond, &square(), &b).unwrap();
+
+  assert_close(manifold.normal, v(0.0, 1.0));
+  assert_eq!(manifold.points().len(), 1);
+  let contact = manifold.points()[0];
+  assert!((contact.separation + 0.2).abs() < 1e-5);
+  assert_close(contact.point, v(0.0, top - 0.1));
+
+  // B owns the reference face, A the vertex, as seen from A and B
+  assert_eq!((contact.id.type_a, contact.id.type_b), (FeatureType::Vertex, FeatureType::Face));
+}
+
+#[test]
+fn feature_ids_persist_under_small_motion() {
+  let ids = |x: f32, angle: f32| {
+    let manifold = collide_polygons(&square(), &at(0.0, 0.0, 0.0), &square(), &at(x, 1.95, angle)).unwrap();
+    manifold.points().iter().map(|c| c.id).collect::<HashSet<_>>()
+  };
+
+  assert_eq!(ids(0.5, 0.0), ids(0.52, 0.01));
+  assert_eq!(ids(0.5, 0.0), ids(0.48, -0.01));
+}
+
+#[test]
+fn deepest_contact_matches_epa_on_random_polygons() {
+  let mut checked = 0;
+
+  for ((a, transform_a), (b, transform_b)) in random_placed_pairs(24, 500, 1.0) {
+    let result = gjk::distance(&a, &transform_a, &b, &transform_b);
+    let manifold = collide_polygons(&a, &transform_a, &b, &transform_b);
+    if !result.intersecting {
+      assert!(manifold.is_none());
+      continue;
+    }
+
+    let penetration = epa::penetration(&a, &transform_a, &b, &transform_b, &result.simplex).unwrap();
+    if penetration.depth < 1e-3 { continue; }
+
+    // no contact is deeper than the penetration, and the reference face is
+    // within the hysteresis tolerance of the axis of least penetration
+    let manifold = manifold.unwrap();
+    let deepest = manifold.points().iter().map(|c| c.separation).fold(f32::INFINITY, f32::min);
+    assert!(deepest >= -penetration.depth - 1e-3, "{deepest} < -{}", penetration.depth);
+    assert!((manifold.normal.norm() - 1.0).abs() < 1e-5);
+    checked += 1;
+  }
+
+  assert!(checked > 100);
+}
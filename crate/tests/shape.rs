@@ -0,0 +1,201 @@
+//! Native test suite for `geom::Shape`.
+
+#[path = "support/geom.rs"]
+mod support;
+
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use nalgebra::Vector2;
+use support::{assert_close, assert_near, at, v};
+use wasm_physics::geom::{collide, ConvexPoly, Shape};
+
+/// one of each convex shape, all roughly half a unit across
+fn convex_shapes() -> Vec<Shape> {
+  vec![
+    Shape::Circle { radius: 0.4 },
+    Shape::Capsule { a: v(-0.3, 0.0), b: v(0.3, 0.0), radius: 0.2 },
+    Shape::Segment { a: v(-0.5, 0.0), b: v(0.5, 0.0) },
+    Shape::cuboid(0.4, 0.3),
+    Shape::Polygon(ConvexPoly::regular(5, 0.4)),
+  ]
+}
+
+#[test]
+fn aabb_of_each_variant() {
+  let circle = Shape::Circle { radius: 0.5 }.aabb(&at(1.0, 2.0, 0.7));
+  assert_close(circle.lower_bound, v(0.5, 1.5));
+  assert_close(circle.upper_bound, v(1.5, 2.5));
+
+  let capsule = Shape::Capsule { a: v(-1.0, 0.0), b: v(1.0, 0.0), radius: 0.5 };
+  let volume = capsule.aabb(&at(0.0, 0.0, FRAC_PI_2));
+  assert_close(volume.lower_bound, v(-0.5, -1.5));
+  assert_close(volume.upper_bound, v(0.5, 1.5));
+
+  let diagonal = 2.0_f32.sqrt();
+  let volume = Shape::cuboid(1.0, 1.0).aabb(&at(0.0, 0.0, FRAC_PI_4));
+  assert_close(volume.lower_bound, v(-diagonal, -diagonal));
+  assert_close(volume.upper_bound, v(diagonal, diagonal));
+
+  let compound = Shape::Compound(vec![
+    (at(-2.0, 0.0, 0.0), Shape::Circle { radius: 1.0 }),
+    (at(2.0, 0.0, 0.0), Shape::Segment { a: v(0.0, -3.0), b: v(0.0, 3.0) }),
+  ]);
+  let volume = compound.aabb(&at(1.0, 0.0, 0.0));
+  assert_close(volume.lower_bound, v(-2.0, -3.0));
+  assert_close(volume.upper_bound, v(3.0, 3.0));
+}
+
+#[test]
+fn mass_properties_match_polygons_and_closed_forms() {
+  let density = 2.0;
+
+  let circle = Shape::Circle { radius: 0.5 }.mass_properties(density);
+  assert_near(circle.mass, density * PI * 0.25, 1e-6);
+  assert_near(circle.inertia, 0.5 * circle.mass * 0.25, 1e-6);
+
+  // a box is the polygon with the same corners
+  let box_mass = Shape::cuboid(0.5, 0.25).mass_properties(density);
+  let poly = ConvexPoly::try_new([v(-0.5, -0.25), v(0.5, -0.25), v(0.5, 0.25), v(-0.5, 0.25)]).unwrap();
+  let poly_mass = Shape::Polygon(poly).mass_properties(density);
+  assert_near(box_mass.mass, poly_mass.mass, 1e-5);
+  assert_near(box_mass.inertia, poly_mass.inertia, 1e-5);
+
+  // a capsule is close to a finely tessellated polygon of its outline
+  let capsule = Shape::Capsule { a: v(-0.5, 0.25), b: v(0.5, 0.25), radius: 0.3 };
+  let outline = capsule.outlines(2000).remove(0);
+  let tessellated = ConvexPoly::from_points(outline).unwrap().mass_properties(density);
+  let exact = capsule.mass_properties(density);
+  assert_near(exact.mass, tessellated.mass, 1e-4);
+  assert_near(exact.inertia, tessellated.inertia, 1e-3);
+  assert_close(exact.center, v(0.0, 0.25));
+
+  // two boxes side by side make one long box
+  let halves = Shape::Compound(vec![
+    (at(-0.5, 0.0, 0.0), Shape::cuboid(0.5, 0.25)),
+    (at(0.5, 0.0, 0.0), Shape::cuboid(0.5, 0.25)),
+  ]);
+  let whole = Shape::cuboid(1.0, 0.25).mass_properties(density);
+  let combined = halves.mass_properties(density);
+  assert_near(combined.mass, whole.mass, 1e-5);
+  assert_near(combined.inertia, whole.inertia, 1e-5);
+  assert_close(combined.center, Vector2::zeros());
+
+  assert_eq!(Shape::Segment { a: v(0.0, 0.0), b: v(1.0, 0.0) }.mass_properties(density).mass, 0.0);
+}
+
+#[test]
+fn outlines_are_counter_clockwise() {
+  for shape in convex_shapes() {
+    let outline = shape.outlines(16).remove(0);
+    if let Shape::Segment { .. } = shape {
+      assert_eq!(outline.len(), 2);
+      continue;
+    }
+    assert!(ConvexPoly::try_new(outline).is_ok(), "{shape:?}");
+  }
+
+  let circle = Shape::Circle { radius: 2.0 }.outlines(12).remove(0);
+  assert_eq!(circle.len(), 12);
+  assert!(circle.iter().all(|p| (p.norm() - 2.0).abs() < 1e-5));
+
+  let compound = Shape::Compound(vec![
+    (at(1.0, 0.0, 0.0), Shape::cuboid(0.5, 0.5)),
+    (at(-1.0, 0.0, 0.0), Shape::Circle { radius: 0.5 }),
+  ]);
+  let outlines = compound.outlines(8);
+  assert_eq!(outlines.len(), 2);
+  assert_close(outlines[0][0], v(0.5, -0.5));
+  assert_close(outlines[1][0], v(-0.5, 0.0));
+}
+
+#[test]
+fn every_pair_of_convex_shapes_collides() {
+  for a in convex_shapes() {
+    for b in convex_shapes() {
+      // overlapping, with B turned so that two segments cross
+      let contacts = collide(&a, &at(0.0, 0.0, 0.0), &b, &at(0.2, 0.1, 1.0));
+      assert_eq!(contacts.len(), 1, "{a:?} {b:?}");
+      let manifold = &contacts[0].manifold;
+      assert!((manifold.normal.norm() - 1.0).abs() < 1e-5, "{a:?} {b:?}");
+      assert!(!manifold.points().is_empty());
+      assert!(manifold.points().iter().all(|c| c.separation < 0.0), "{a:?} {b:?}");
+
+      assert!(collide(&a, &at(0.0, 0.0, 0.0), &b, &at(3.0, 3.0, 1.0)).is_empty());
+    }
+  }
+}
+
+#[test]
+fn rounded_shapes_collide_exactly() {
+  let circle = Shape::Circle { radius: 0.5 };
+  let contacts = collide(&circle, &at(0.0, 0.0, 0.0), &circle, &at(0.6, 0.8, 0.0));
+  let manifold = &contacts[0].manifold;
+  assert_close(manifold.normal, v(0.6, 0.8));
+  assert_near(manifold.points()[0].separation, 0.0, 1e-5);
+  assert_close(manifold.points()[0].point, v(0.3, 0.4));
+
+  // a capsule lying on a box, sunk 0.1 into it
+  let ground = Shape::cuboid(2.0, 0.5);
+  let capsule = Shape::Capsule { a: v(-0.5, 0.0), b: v(0.5, 0.0), radius: 0.25 };
+  let contacts = collide(&ground, &at(0.0, 0.0, 0.0), &capsule, &at(0.3, 0.65, 0.0));
+  let manifold = &contacts[0].manifold;
+  assert_close(manifold.normal, v(0.0, 1.0));
+  assert_near(manifold.points()[0].separation, -0.1, 1e-4);
+  assert_near(manifold.points()[0].point.y, 0.45, 1e-4);
+
+  // a circle whose center is inside a polygon
+  let contacts = collide(&ground, &at(0.0, 0.0, 0.0), &circle, &at(1.0, 0.4, 0.0));
+  let manifold = &contacts[0].manifold;
+  assert_close(manifold.normal, v(0.0, 1.0));
+  assert_near(manifold.points()[0].separation, -0.6, 1e-4);
+}
+
+#[test]
+fn boxes_with_zero_extents_collapse_to_segments_and_points() {
+  let wall = Shape::cuboid(0.0, 1.0);
+  let volume = wall.aabb(&at(1.0, 0.0, FRAC_PI_2));
+  assert_close(volume.lower_bound, v(0.0, 0.0));
+  assert_close(volume.upper_bound, v(2.0, 0.0));
+  assert_eq!(wall.mass_properties(1.0).mass, 0.0);
+
+  // a circle touching the wall from the side, as if it were a segment
+  let circle = Shape::Circle { radius: 0.5 };
+  let contacts = collide(&wall, &at(0.0, 0.0, 0.0), &circle, &at(0.4, 0.5, 0.0));
+  let manifold = &contacts[0].manifold;
+  assert_close(manifold.normal, v(1.0, 0.0));
+  assert_near(manifold.points()[0].separation, -0.1, 1e-4);
+
+  // a box with no extent at all is a point
+  let point = Shape::cuboid(0.0, 0.0);
+  let volume = point.aabb(&at(1.0, 2.0, 0.3));
+  assert_close(volume.lower_bound, v(1.0, 2.0));
+  assert_close(volume.upper_bound, v(1.0, 2.0));
+  assert_eq!(collide(&point, &at(0.0, 0.0, 0.0), &Shape::cuboid(1.0, 1.0), &at(0.5, 0.0, 0.0)).len(), 1);
+  assert!(collide(&point, &at(0.0, 0.0, 0.0), &circle, &at(1.0, 0.0, 0.0)).is_empty());
+}
+
+#[test]
+fn compound_contacts_name_their_children() {
+  let dumbbell = Shape::Compound(vec![
+    (at(-1.0, 0.0, 0.0), Shape::Circle { radius: 0.5 }),
+    (at(0.0, 0.0, 0.0), Shape::Segment { a: v(-1.0, 0.0), b: v(1.0, 0.0) }),
+    (at(1.0, 0.0, 0.0), Shape::Circle { radius: 0.5 }),
+  ]);
+  let floor = Shape::cuboid(5.0, 0.5);
+
+  // resting on both weights, with the bar clear of the floor
+  let contacts = collide(&dumbbell, &at(0.0, 0.9, 0.0), &floor, &at(0.0, 0.0, 0.0));
+  let mut children: Vec<usize> = contacts.iter().map(|c| c.child_a).collect();
+  children.sort();
+  assert_eq!(children, vec![0, 2]);
+  assert!(contacts.iter().all(|c| c.child_b == 0));
+  for contact in &contacts {
+    assert_close(contact.manifold.normal, v(0.0, -1.0));
+    assert_near(contact.manifold.points()[0].separation, -0.1, 1e-4);
+  }
+
+  // compound against compound
+  let contacts = collide(&dumbbell, &at(0.0, 0.0, 0.0), &dumbbell, &at(0.0, 0.9, 0.0));
+  assert_eq!(contacts.len(), 2);
+  assert!(contacts.iter().all(|c| c.child_a == c.child_b));
+}
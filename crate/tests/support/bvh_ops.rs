@@ -0,0 +1,161 @@
+//! Random operation sequences for the dynamic AABB tree, checked against a
+//! brute-force linear scan.  Shared by the property tests and the fuzz target.
+
+use nalgebra::Vector2;
+use wasm_physics::bvh::aabb::{NodeIdx, Tree, AABB};
+
+#[derive(Debug, Clone)]
+pub enum Op {
+  Insert(AABB),
+  /// remove the live leaf at this index, modulo the number of live leaves
+  Remove(usize),
+  /// move the live leaf at this index to a new volume with a displacement
+  Move(usize, AABB, Vector2<f32>),
+  QueryAabb(AABB),
+  QueryPoint(Vector2<f32>),
+  RayCast(Vector2<f32>, Vector2<f32>),
+  BoxCast(AABB, Vector2<f32>),
+  Rebuild,
+}
+
+fn next_byte(bytes: &mut &[u8]) -> Option<u8> {
+  let (&first, rest) = bytes.split_first()?;
+  *bytes = rest;
+  Some(first)
+}
+
+fn next_point(bytes: &mut &[u8]) -> Option<Vector2<f32>> {
+  let x = next_byte(bytes)?;
+  let y = next_byte(bytes)?;
+  Some(Vector2::new((x as f32 - 128.0) / 8.0, (y as f32 - 128.0) / 8.0))
+}
+
+fn next_volume(bytes: &mut &[u8]) -> Option<AABB> {
+  let a = next_point(bytes)?;
+  let b = next_point(bytes)?;
+  Some(AABB::new(a.inf(&b), a.sup(&b)))
+}
+
+/// Decodes an operation from the front of `bytes`, consuming what it reads.
+/// Coordinates are quantized so that overlaps and shared edges are common.
+pub fn decode_op(bytes: &mut &[u8]) -> Option<Op> {
+  let opcode = next_byte(bytes)?;
+  let index = next_byte(bytes)? as usize;
+
+  let op = match opcode % 8 {
+    0 => Op::Insert(next_volume(bytes)?),
+    1 => Op::Remove(index),
+    2 => Op::Move(index, next_volume(bytes)?, next_point(bytes)? / 8.0),
+    3 => Op::QueryAabb(next_volume(bytes)?),
+    4 => Op::QueryPoint(next_point(bytes)?),
+    5 => Op::RayCast(next_point(bytes)?, next_point(bytes)?),
+    6 => Op::BoxCast(next_volume(bytes)?, next_point(bytes)?),
+    _ => Op::Rebuild,
+  };
+
+  Some(op)
+}
+
+/// A tree alongside the list of leaves it should contain.
+pub struct Harness {
+  pub tree: Tree<u32>,
+  /// `(data, handle, tight volume)` for every live leaf
+  pub live: Vec<(u32, NodeIdx, AABB)>,
+  next_data: u32,
+}
+
+impl Harness {
+  pub fn new() -> Self {
+    Harness { tree: Tree::new(), live: vec![], next_data: 0 }
+  }
+
+  /// stored volumes of the live leaves which satisfy `test`
+  fn brute_force<T: Fn(&AABB) -> bool>(&self, test: T) -> Vec<u32> {
+    let mut result: Vec<u32> = self.live.iter()
+      .filter(|(_, handle, _)| test(self.tree.fat_volume(*handle).unwrap()))
+      .map(|(data, _, _)| *data)
+      .collect();
+    result.sort();
+    result
+  }
+
+  /// applies an operation to the tree, panicking on any discrepancy
+  pub fn apply(&mut self, op: &Op) {
+    match *op {
+      Op::Insert(volume) => {
+        let handle = self.tree.insert_leaf(volume, self.next_data);
+        self.live.push((self.next_data, handle, volume));
+        self.next_data += 1;
+      }
+      Op::Remove(index) => {
+        if self.live.is_empty() { return; }
+        let (data, handle, _) = self.live.swap_remove(index % self.live.len());
+        assert_eq!(self.tree.remove_leaf(handle), Some(data));
+        assert_eq!(self.tree.remove_leaf(handle), None);
+      }
+      Op::Move(index, volume, displacement) => {
+        if self.live.is_empty() { return; }
+        let index = index % self.live.len();
+        let handle = self.live[index].1;
+        self.tree.move_leaf(handle, volume, displacement);
+        self.live[index].2 = volume;
+      }
+      Op::QueryAabb(query) => {
+        let mut result = vec![];
+        self.tree.query_aabb(&query, |_, &data| { result.push(data); true });
+        result.sort();
+        assert_eq!(result, self.brute_force(|v| v.overlaps(&query)), "{op:?}");
+      }
+      Op::QueryPoint(point) => {
+        let mut result = vec![];
+        self.tree.query_point(point, |_, &data| { result.push(data); true });
+        result.sort();
+        assert_eq!(result, self.brute_force(|v| v.contains_point(point)), "{op:?}");
+      }
+      Op::RayCast(p1, p2) => {
+        // every leaf entered by the ray, without clipping
+        let mut result = vec![];
+        self.tree.ray_cast(p1, p2, 1.0, |_, &data, _| { result.push(data); -1.0 });
+        result.sort();
+        assert_eq!(result, self.brute_force(|v| v.ray_cast(p1, p2).is_some()), "{op:?}");
+
+        // closest leaf entered by the ray, with clipping
+        let mut closest: Option<f32> = None;
+        self.tree.ray_cast(p1, p2, 1.0, |_, _, fraction| {
+          closest = Some(closest.map_or(fraction, |c| c.min(fraction)));
+          fraction
+        });
+        let expected = self.live.iter()
+          .filter_map(|(_, handle, _)| self.tree.fat_volume(*handle).unwrap().ray_cast(p1, p2))
+          .reduce(f32::min);
+        assert_eq!(closest, expected, "{op:?}");
+      }
+      Op::BoxCast(volume, displacement) => {
+        let mut result = vec![];
+        self.tree.box_cast(&volume, displacement, |_, &data, _| { result.push(data); -1.0 });
+        result.sort();
+        assert_eq!(result, self.brute_force(|v| v.box_cast(&volume, displacement).is_some()), "{op:?}");
+
+        let mut first: Option<f32> = None;
+        self.tree.box_cast(&volume, displacement, |_, _, fraction| {
+          first = Some(first.map_or(fraction, |f| f.min(fraction)));
+          fraction
+        });
+        let expected = self.live.iter()
+          .filter_map(|(_, handle, _)| self.tree.fat_volume(*handle).unwrap().box_cast(&volume, displacement))
+          .reduce(f32::min);
+        assert_eq!(first, expected, "{op:?}");
+      }
+      Op::Rebuild => {
+        self.tree.rebuild();
+      }
+    }
+
+    self.tree.validate().unwrap();
+
+    // every leaf's stored volume must still contain its tight volume
+    for (_, handle, volume) in &self.live {
+      assert!(self.tree.fat_volume(*handle).unwrap().contains(volume));
+    }
+  }
+}
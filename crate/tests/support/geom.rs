@@ -0,0 +1,67 @@
+//! Helpers shared by the native test suites for `geom`.
+
+#![allow(dead_code)]
+
+use nalgebra::{Isometry2, Point2, Vector2};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use wasm_physics::geom::ConvexPoly;
+
+pub fn v(x: f32, y: f32) -> Vector2<f32> {
+  Vector2::new(x, y)
+}
+
+/// the square with corners at `(±1, ±1)`
+pub fn square() -> ConvexPoly {
+  ConvexPoly::try_new([v(-1.0, -1.0), v(1.0, -1.0), v(1.0, 1.0), v(-1.0, 1.0)]).unwrap()
+}
+
+pub fn at(x: f32, y: f32, angle: f32) -> Isometry2<f32> {
+  Isometry2::new(v(x, y), angle)
+}
+
+pub fn assert_close(a: Vector2<f32>, b: Vector2<f32>) {
+  assert!((a - b).norm() < 1e-4, "{a:?} != {b:?}");
+}
+
+/// asserts that `a` is within `tolerance` of `b`, relative to `b` when it is
+/// larger than one
+pub fn assert_near(a: f32, b: f32, tolerance: f32) {
+  assert!((a - b).abs() <= tolerance * b.abs().max(1.0), "{a} != {b}");
+}
+
+/// world space points of a placed polygon
+pub fn world_points(poly: &ConvexPoly, transform: &Isometry2<f32>) -> Vec<Vector2<f32>> {
+  (0..poly.num_points()).map(|i| (transform * Point2::from(poly.point(i))).coords).collect()
+}
+
+/// A polygon and its placement in the world.
+pub type Placed = (ConvexPoly, Isometry2<f32>);
+
+/// Seeded random pairs of placed polygons.  Each polygon is the hull of up to
+/// eight points in the square with corners at `(±1, ±1)`, and is placed with
+/// a random rotation at a random offset of up to `spread` along each axis.
+/// Attempts where either hull is degenerate are skipped, so fewer than
+/// `attempts` pairs are returned.
+pub fn random_placed_pairs(seed: u64, attempts: usize, spread: f32) -> Vec<(Placed, Placed)> {
+  let mut rng = StdRng::seed_from_u64(seed);
+  let mut pairs = vec![];
+
+  for _ in 0..attempts {
+    let mut random_poly = || {
+      let n = rng.gen_range(3, 9);
+      ConvexPoly::from_points((0..n).map(|_| v(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0))))
+    };
+    let (a, b) = match (random_poly(), random_poly()) {
+      (Ok(a), Ok(b)) => (a, b),
+      _ => continue
+    };
+    let mut random_transform = || at(
+      rng.gen_range(-spread, spread), rng.gen_range(-spread, spread), rng.gen_range(-3.0, 3.0)
+    );
+    let (transform_a, transform_b) = (random_transform(), random_transform());
+    pairs.push(((a, transform_a), (b, transform_b)));
+  }
+
+  pairs
+}